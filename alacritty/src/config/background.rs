@@ -0,0 +1,158 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use log::warn;
+use serde::Serialize;
+
+use alacritty_config_derive::ConfigDeserialize;
+
+use crate::config::LOG_TARGET_CONFIG;
+use crate::config::ui_config::{Delta, Percentage};
+
+#[derive(ConfigDeserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct BackgroundAnimationConfig {
+    /// Image rendered as glyphs behind the terminal.
+    pub path: Option<PathBuf>,
+
+    /// Source of the glyph colors for the image.
+    pub color_mode: BackgroundColorMode,
+
+    /// Glyph ramp the image is rendered with.
+    pub gradient: BackgroundGradient,
+
+    /// Frames per second, overriding the animation's own timing.
+    fps: Option<u16>,
+
+    /// Opacity of the animated glyphs.
+    pub opacity: Percentage,
+
+    /// Draw the animated glyph with dimmed colors.
+    pub dim: bool,
+
+    /// Underline the animated glyph in its foreground color.
+    pub underline: bool,
+
+    /// Fade-in duration of the animated glyph in milliseconds.
+    fade_in: u16,
+
+    /// Pan of the animated glyph in cells per second.
+    drift: Option<Delta<f32>>,
+}
+
+impl Default for BackgroundAnimationConfig {
+    fn default() -> Self {
+        Self::from_parts(None, Default::default(), BackgroundGradient::DEFAULT_NAME, None, 1.)
+    }
+}
+
+impl BackgroundAnimationConfig {
+    /// Build a config from its image settings, leaving the rest at their defaults.
+    ///
+    /// Unknown gradient names fall back to the default gradient, an `fps` of zero keeps the
+    /// animation's own timing, and the opacity is clamped between `0.0` and `1.0`.
+    pub fn from_parts(
+        path: Option<PathBuf>,
+        color_mode: BackgroundColorMode,
+        gradient: &str,
+        fps: Option<u16>,
+        opacity: f32,
+    ) -> Self {
+        let gradient = BackgroundGradient::from_name(gradient).unwrap_or_else(|| {
+            warn!(
+                target: LOG_TARGET_CONFIG,
+                "Unknown background gradient `{gradient}`, using `{}` instead",
+                BackgroundGradient::DEFAULT_NAME
+            );
+            BackgroundGradient::default()
+        });
+        let opacity =
+            if opacity.is_nan() { Percentage::default() } else { Percentage::new(opacity) };
+
+        Self {
+            path,
+            color_mode,
+            gradient,
+            fps: fps.filter(|&fps| fps > 0),
+            opacity,
+            dim: true,
+            underline: false,
+            fade_in: Default::default(),
+            drift: Default::default(),
+        }
+    }
+
+    /// Interval between animation steps when the frame rate is overridden.
+    pub fn frame_interval(&self) -> Option<Duration> {
+        self.fps.filter(|&fps| fps > 0).map(|fps| Duration::from_secs(1) / u32::from(fps))
+    }
+
+    pub fn fade_in(&self) -> Duration {
+        Duration::from_millis(self.fade_in as u64)
+    }
+
+    /// Drift velocity as `(columns, lines)` per second.
+    pub fn drift(&self) -> Option<(f32, f32)> {
+        self.drift.map(|drift| (drift.x, drift.y))
+    }
+}
+
+/// Source of the background glyph colors.
+#[derive(ConfigDeserialize, Serialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BackgroundColorMode {
+    /// Gray levels matching the brightness each glyph was picked for.
+    Luminance,
+    /// The image's own colors and transparency.
+    #[default]
+    ColorAlpha,
+}
+
+/// Glyph ramps for rendering the background image, from sparse to dense.
+#[derive(ConfigDeserialize, Serialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BackgroundGradient {
+    #[default]
+    Detailed,
+    Standard,
+    Blocks,
+    Binary,
+}
+
+impl BackgroundGradient {
+    const DEFAULT_NAME: &'static str = "detailed";
+
+    /// Gradient with the given case-insensitive name, such as `"blocks"`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "detailed" => Some(Self::Detailed),
+            "standard" => Some(Self::Standard),
+            "blocks" => Some(Self::Blocks),
+            "binary" => Some(Self::Binary),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_parts_validates() {
+        let config =
+            BackgroundAnimationConfig::from_parts(None, Default::default(), "sparkles", None, 1.);
+        assert_eq!(BackgroundGradient::Detailed, config.gradient);
+        assert_eq!(BackgroundAnimationConfig::default(), config);
+
+        let path = Some(PathBuf::from("wallpaper.gif"));
+        let color_mode = BackgroundColorMode::Luminance;
+        let config = BackgroundAnimationConfig::from_parts(path, color_mode, "Blocks", Some(0), 2.);
+        assert_eq!(BackgroundGradient::Blocks, config.gradient);
+        assert_eq!(BackgroundColorMode::Luminance, config.color_mode);
+        assert_eq!(None, config.frame_interval());
+        assert_eq!(1., config.opacity.as_f32());
+
+        let config =
+            BackgroundAnimationConfig::from_parts(None, Default::default(), "binary", Some(4), -1.);
+        assert_eq!(Some(Duration::from_millis(250)), config.frame_interval());
+        assert_eq!(0., config.opacity.as_f32());
+    }
+}
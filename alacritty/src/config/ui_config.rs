@@ -19,6 +19,7 @@ use alacritty_terminal::term::search::RegexSearch;
 use alacritty_terminal::tty::{Options as PtyOptions, Shell};
 
 use crate::config::LOG_TARGET_CONFIG;
+use crate::config::background::BackgroundAnimationConfig;
 use crate::config::bell::BellConfig;
 use crate::config::bindings::{
     self, Action, Binding, BindingKey, KeyBinding, KeyLocation, ModeWrapper, ModsWrapper,
@@ -72,6 +73,9 @@ pub struct UiConfig {
     /// Bell configuration.
     pub bell: BellConfig,
 
+    /// Background animation configuration.
+    pub background_animation: BackgroundAnimationConfig,
+
     /// RGB values for colors.
     pub colors: Colors,
 
@@ -3,6 +3,7 @@ use std::time::{Duration, Instant};
 use alacritty_terminal::grid::Dimensions;
 use alacritty_terminal::index::{Column, Point};
 
+use crate::config::background::BackgroundAnimationConfig;
 use crate::display::SizeInfo;
 use crate::display::color::List;
 use crate::display::content::RenderableCell;
@@ -18,15 +19,36 @@ pub struct BackgroundAnimation {
     column: Option<usize>,
     row: usize,
     last_update: Instant,
+    started: Instant,
+    /// Opacity of the glyph while it fades in, from `0.0` to `1.0`.
+    alpha: f32,
+    /// Cells the glyph has drifted by, as `(columns, lines)`.
+    offset: (usize, usize),
+    config: BackgroundAnimationConfig,
 }
 
 impl BackgroundAnimation {
-    pub fn new(size: &SizeInfo) -> Self {
-        let mut animation = Self { glyph: 'a', column: None, row: 0, last_update: Instant::now() };
+    pub fn new(size: &SizeInfo, config: BackgroundAnimationConfig) -> Self {
+        let now = Instant::now();
+        let mut animation = Self {
+            glyph: 'a',
+            column: None,
+            row: 0,
+            last_update: now,
+            started: now,
+            alpha: 0.,
+            offset: (0, 0),
+            config,
+        };
+        animation.alpha = animation.fade_progress(now);
         animation.on_resize(size);
         animation
     }
 
+    pub fn update_config(&mut self, config: BackgroundAnimationConfig) {
+        self.config = config;
+    }
+
     pub fn on_resize(&mut self, size: &SizeInfo) {
         if size.screen_lines() == 0 || size.columns() == 0 {
             self.column = None;
@@ -49,19 +71,32 @@ impl BackgroundAnimation {
             return None;
         }
 
+        let fading = self.alpha < 1.;
+        self.alpha = self.fade_progress(now);
+
         if self.column.is_none() {
             self.column = Some(size.columns().saturating_sub(1));
             self.last_update = now;
+            self.offset = self.drift_offset(now, size);
             return self.current_point(size).map(|point| (None, point));
         }
 
-        if now.duration_since(self.last_update) < ADVANCE_INTERVAL {
-            return None;
+        let old_point = self.current_point(size);
+        let offset = self.drift_offset(now, size);
+        let drifted = offset != self.offset;
+        self.offset = offset;
+
+        let interval = self.config.frame_interval().unwrap_or(ADVANCE_INTERVAL);
+        if now.duration_since(self.last_update) < interval {
+            // Drift can still move the glyph, and it's redrawn while its opacity changes.
+            if !fading && !drifted {
+                return None;
+            }
+            return self.current_point(size).map(|new_point| (old_point, new_point));
         }
 
         self.last_update = now;
 
-        let old_point = self.current_point(size);
         let mut column = self.column.unwrap_or(0);
         if column == 0 {
             column = size.columns().saturating_sub(1);
@@ -75,8 +110,22 @@ impl BackgroundAnimation {
 
     pub fn render_cell(&self, colors: &List, size: &SizeInfo) -> Option<RenderableCell> {
         let point = self.current_point(size)?;
-        let fg = colors[NamedColor::DimForeground];
+        let fg = if self.config.dim {
+            colors[NamedColor::DimForeground]
+        } else {
+            colors[NamedColor::Foreground]
+        };
         let bg = colors[NamedColor::Background];
+        // Fade the glyph in by blending it into the background.
+        let alpha = self.alpha * self.config.opacity.as_f32();
+        let fg = fg * alpha + bg * (1. - alpha);
+
+        let mut flags = Flags::empty();
+        flags.set(Flags::DIM, self.config.dim);
+        flags.set(Flags::UNDERLINE, self.config.underline);
+
+        // The underline takes the glyph's color, but only when there is one to draw.
+        let underline = if self.config.underline { fg } else { bg };
 
         Some(RenderableCell {
             character: self.glyph,
@@ -84,12 +133,36 @@ impl BackgroundAnimation {
             fg,
             bg,
             bg_alpha: 0.0,
-            underline: fg,
-            flags: Flags::DIM,
+            underline,
+            flags,
             extra: None,
         })
     }
 
+    /// Fraction of the fade-in completed at `now`, from `0.0` to `1.0`.
+    fn fade_progress(&self, now: Instant) -> f32 {
+        let duration = self.config.fade_in();
+        let elapsed = now.saturating_duration_since(self.started);
+        if elapsed >= duration {
+            return 1.;
+        }
+
+        elapsed.as_secs_f32() / duration.as_secs_f32()
+    }
+
+    /// Cells the configured drift has moved the glyph by at `now`, wrapped to the grid.
+    fn drift_offset(&self, now: Instant, size: &SizeInfo) -> (usize, usize) {
+        let Some((columns_per_second, lines_per_second)) = self.config.drift() else {
+            return (0, 0);
+        };
+
+        let elapsed = now.saturating_duration_since(self.started).as_secs_f32();
+        let wrap = |velocity: f32, cells: usize| {
+            (velocity * elapsed).floor().rem_euclid(cells.max(1) as f32) as usize
+        };
+        (wrap(columns_per_second, size.columns()), wrap(lines_per_second, size.screen_lines()))
+    }
+
     pub fn is_active(&self, size: &SizeInfo) -> bool {
         size.columns() > 0 && size.screen_lines() > 0 && self.column.is_some()
     }
@@ -105,6 +178,83 @@ impl BackgroundAnimation {
         }
 
         let line = self.row.min(size.screen_lines().saturating_sub(1));
+        let (columns, lines) = self.offset;
+        let column = (column + columns) % size.columns();
+        let line = (line + lines) % size.screen_lines();
         Some(Point::new(line, Column(column)))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::config::color::Colors;
+
+    fn config(toml: &str) -> BackgroundAnimationConfig {
+        toml::from_str(toml).unwrap()
+    }
+
+    fn render(config: BackgroundAnimationConfig) -> RenderableCell {
+        let size = SizeInfo::new(100., 100., 10., 10., 0., 0., false);
+        let animation = BackgroundAnimation::new(&size, config);
+        animation.render_cell(&List::from(&Colors::default()), &size).unwrap()
+    }
+
+    #[test]
+    fn cell_flags_follow_config() {
+        let cell = render(BackgroundAnimationConfig::default());
+        assert_eq!(Flags::DIM, cell.flags);
+
+        let cell = render(config("dim = false"));
+        assert!(!cell.flags.contains(Flags::DIM));
+        assert_eq!(cell.bg, cell.underline);
+
+        let cell = render(config("dim = false\nunderline = true"));
+        assert_eq!(Flags::UNDERLINE, cell.flags);
+        assert_eq!(cell.fg, cell.underline);
+    }
+
+    #[test]
+    fn glyph_fades_in() {
+        let size = SizeInfo::new(100., 100., 10., 10., 0., 0., false);
+        let colors = List::from(&Colors::default());
+        let mut animation = BackgroundAnimation::new(&size, config("fade_in = 400"));
+        let start = animation.started;
+
+        animation.update(start, &size);
+        let cell = animation.render_cell(&colors, &size).unwrap();
+        assert!(animation.alpha < 0.01);
+        assert_eq!(cell.bg, cell.fg);
+
+        // The glyph is redrawn while fading, even when it doesn't move.
+        assert!(animation.update(start + Duration::from_millis(50), &size).is_some());
+
+        animation.update(start + Duration::from_millis(400), &size);
+        let cell = animation.render_cell(&colors, &size).unwrap();
+        assert_eq!(1., animation.alpha);
+        assert_eq!(colors[NamedColor::DimForeground], cell.fg);
+
+        let animation = BackgroundAnimation::new(&size, BackgroundAnimationConfig::default());
+        assert_eq!(1., animation.alpha);
+    }
+
+    #[test]
+    fn drift_pans_glyph() {
+        let size = SizeInfo::new(100., 100., 10., 10., 0., 0., false);
+        let mut animation = BackgroundAnimation::new(&size, BackgroundAnimationConfig::default());
+        let start = animation.started;
+        assert_eq!(None, animation.update(start + Duration::from_millis(50), &size));
+
+        let config = config("drift = { x = 30.0, y = -7.5 }");
+        let mut animation = BackgroundAnimation::new(&size, config);
+        let start = animation.started;
+        let point = animation.current_point(&size);
+        assert_eq!(Some(Point::new(4, Column(9))), point);
+
+        // Before the glyph advances on its own, 100ms of drift move it three columns right and one
+        // line up, wrapping around the right edge.
+        let update = animation.update(start + Duration::from_millis(100), &size);
+        assert_eq!(Some((point, Point::new(3, Column(2)))), update);
+    }
+}
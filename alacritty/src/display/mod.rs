@@ -524,7 +524,10 @@ impl Display {
             renderer_preference: config.debug.renderer,
             surface: ManuallyDrop::new(surface),
             colors: List::from(&config.colors),
-            background_animation: BackgroundAnimation::new(&size_info),
+            background_animation: BackgroundAnimation::new(
+                &size_info,
+                config.background_animation.clone(),
+            ),
             frame_timer: FrameTimer::new(),
             raw_window_handle,
             damage_tracker,
@@ -1075,6 +1078,7 @@ impl Display {
     pub fn update_config(&mut self, config: &UiConfig) {
         self.damage_tracker.debug = config.debug.highlight_damage;
         self.visual_bell.update_config(&config.bell);
+        self.background_animation.update_config(config.background_animation.clone());
         self.colors = List::from(&config.colors);
     }
 
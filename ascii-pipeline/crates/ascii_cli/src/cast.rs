@@ -0,0 +1,75 @@
+//! Streaming writer for asciicast v2 recordings.
+
+use std::io::{self, Write};
+use std::time::Duration;
+
+/// Writes rendered frames as timestamped output events of an asciicast v2 file.
+pub struct CastWriter<W: Write> {
+    writer: W,
+    elapsed: Duration,
+    header_written: bool,
+}
+
+impl<W: Write> CastWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer, elapsed: Duration::ZERO, header_written: false }
+    }
+
+    /// Append a frame of `width`x`height` cells shown for `delay`.
+    ///
+    /// The header is emitted with the dimensions of the first frame.
+    pub fn write_frame(
+        &mut self,
+        text: &str,
+        width: u16,
+        height: u16,
+        delay: Duration,
+    ) -> io::Result<()> {
+        if !self.header_written {
+            let header = serde_json::json!({ "version": 2, "width": width, "height": height });
+            writeln!(self.writer, "{}", header)?;
+            self.header_written = true;
+        }
+
+        // Clear the screen, then draw the frame from the top-left corner.
+        let data = format!("\x1b[2J\x1b[H{}", text.replace('\n', "\r\n"));
+        let event = serde_json::json!([self.elapsed.as_secs_f64(), "o", data]);
+        writeln!(self.writer, "{}", event)?;
+
+        self.elapsed += delay;
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+
+    #[test]
+    fn cast_events_are_ordered() {
+        let mut cast = CastWriter::new(Vec::new());
+        for (text, delay) in [("ab\ncd", 50), ("ef\ngh", 120), ("ij\nkl", 0)] {
+            cast.write_frame(text, 2, 2, Duration::from_millis(delay)).unwrap();
+        }
+
+        let output = String::from_utf8(cast.into_inner()).unwrap();
+        let lines: Vec<Value> =
+            output.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+
+        assert_eq!(4, lines.len());
+        assert_eq!(2, lines[0]["version"]);
+        assert_eq!(2, lines[0]["width"]);
+        assert_eq!(2, lines[0]["height"]);
+
+        let times: Vec<f64> = lines[1..].iter().map(|event| event[0].as_f64().unwrap()).collect();
+        assert_eq!(vec![0.0, 0.05, 0.17], times);
+        assert!(lines[1..].iter().all(|event| event[1] == "o"));
+        assert_eq!("\x1b[2J\x1b[Hef\r\ngh", lines[2][2]);
+    }
+}
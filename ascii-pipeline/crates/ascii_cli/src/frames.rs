@@ -0,0 +1,211 @@
+//! Animation frame loading for the supported input formats.
+
+#[cfg(any(feature = "gif", feature = "png", feature = "tiff"))]
+use std::fs::File;
+#[cfg(feature = "png")]
+use std::io::BufReader;
+#[cfg(feature = "tiff")]
+use std::io::{Read, Seek};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+#[cfg(feature = "gif")]
+use image::codecs::gif::GifDecoder;
+#[cfg(feature = "png")]
+use image::codecs::png::PngDecoder;
+#[cfg(any(feature = "gif", feature = "png"))]
+use image::AnimationDecoder;
+use image::Frame;
+#[cfg(feature = "tiff")]
+use image::{DynamicImage, ImageBuffer, Luma, LumaA, Rgb, RgbImage, Rgba, RgbaImage};
+use walkdir::WalkDir;
+
+/// Load all frames of the animation at `path`.
+///
+/// Directories are read as one frame per image file, in path order. GIF, APNG and multi-page TIFF
+/// files yield one frame per animation frame or page; any other image is a single frame.
+///
+/// GIF and APNG frames keep their source delays. Frames from directories, TIFF pages and still
+/// images have a zero delay, which `animate` replaces with `--fps` timing.
+pub fn load_frames(path: &Path) -> Result<Vec<Frame>> {
+    if path.is_dir() {
+        return load_frames_from_directory(path);
+    }
+
+    let extension = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "gif" => load_frames_from_gif(path),
+        "png" | "apng" => load_frames_from_png(path),
+        "tif" | "tiff" => load_frames_from_tiff(path),
+        _ => load_still_frame(path),
+    }
+}
+
+fn load_still_frame(path: &Path) -> Result<Vec<Frame>> {
+    let image = image::open(path).with_context(|| format!("failed to open image {:?}", path))?;
+    Ok(vec![Frame::new(image.into_rgba8())])
+}
+
+#[cfg(feature = "gif")]
+fn load_frames_from_gif(path: &Path) -> Result<Vec<Frame>> {
+    let file = File::open(path).with_context(|| format!("failed to open GIF {:?}", path))?;
+    let decoder =
+        GifDecoder::new(file).with_context(|| format!("failed to decode GIF {:?}", path))?;
+    let frames = decoder
+        .into_frames()
+        .collect_frames()
+        .with_context(|| format!("failed to collect frames from {:?}", path))?;
+    Ok(frames)
+}
+
+#[cfg(not(feature = "gif"))]
+fn load_frames_from_gif(path: &Path) -> Result<Vec<Frame>> {
+    missing_feature(path, "gif")
+}
+
+/// Load an APNG's frames, or a plain PNG as a single frame.
+#[cfg(feature = "png")]
+fn load_frames_from_png(path: &Path) -> Result<Vec<Frame>> {
+    let file = File::open(path).with_context(|| format!("failed to open PNG {:?}", path))?;
+    let decoder = PngDecoder::new(BufReader::new(file))
+        .with_context(|| format!("failed to decode PNG {:?}", path))?;
+    if !decoder.is_apng() {
+        return load_still_frame(path);
+    }
+
+    let frames = decoder
+        .apng()
+        .into_frames()
+        .collect_frames()
+        .with_context(|| format!("failed to collect frames from {:?}", path))?;
+    Ok(frames)
+}
+
+#[cfg(not(feature = "png"))]
+fn load_frames_from_png(path: &Path) -> Result<Vec<Frame>> {
+    missing_feature(path, "png")
+}
+
+#[cfg(feature = "tiff")]
+fn load_frames_from_tiff(path: &Path) -> Result<Vec<Frame>> {
+    let file = File::open(path).with_context(|| format!("failed to open TIFF {:?}", path))?;
+    decode_tiff_pages(file).with_context(|| format!("failed to decode TIFF {:?}", path))
+}
+
+#[cfg(not(feature = "tiff"))]
+fn load_frames_from_tiff(path: &Path) -> Result<Vec<Frame>> {
+    missing_feature(path, "tiff")
+}
+
+/// Decode every page of a TIFF into a frame.
+///
+/// TIFF pages carry no timing information, so the frames have no delay.
+#[cfg(feature = "tiff")]
+fn decode_tiff_pages<R: Read + Seek>(reader: R) -> Result<Vec<Frame>> {
+    use tiff::decoder::{Decoder, DecodingResult};
+    use tiff::ColorType;
+
+    let mut decoder = Decoder::new(reader)?;
+    let mut frames = Vec::new();
+    loop {
+        let (width, height) = decoder.dimensions()?;
+        let color_type = decoder.colortype()?;
+        let image = match (color_type, decoder.read_image()?) {
+            (ColorType::Gray(8), DecodingResult::U8(data)) => {
+                ImageBuffer::<Luma<u8>, _>::from_raw(width, height, data).map(DynamicImage::from)
+            },
+            (ColorType::GrayA(8), DecodingResult::U8(data)) => {
+                ImageBuffer::<LumaA<u8>, _>::from_raw(width, height, data).map(DynamicImage::from)
+            },
+            (ColorType::RGB(8), DecodingResult::U8(data)) => {
+                RgbImage::from_raw(width, height, data).map(DynamicImage::from)
+            },
+            (ColorType::RGBA(8), DecodingResult::U8(data)) => {
+                RgbaImage::from_raw(width, height, data).map(DynamicImage::from)
+            },
+            (ColorType::Gray(16), DecodingResult::U16(data)) => {
+                ImageBuffer::<Luma<u16>, _>::from_raw(width, height, data).map(DynamicImage::from)
+            },
+            (ColorType::GrayA(16), DecodingResult::U16(data)) => {
+                ImageBuffer::<LumaA<u16>, _>::from_raw(width, height, data).map(DynamicImage::from)
+            },
+            (ColorType::RGB(16), DecodingResult::U16(data)) => {
+                ImageBuffer::<Rgb<u16>, _>::from_raw(width, height, data).map(DynamicImage::from)
+            },
+            (ColorType::RGBA(16), DecodingResult::U16(data)) => {
+                ImageBuffer::<Rgba<u16>, _>::from_raw(width, height, data).map(DynamicImage::from)
+            },
+            (color_type, _) => anyhow::bail!("unsupported TIFF color type {:?}", color_type),
+        };
+
+        let image = image.context("TIFF page data does not match its dimensions")?;
+        frames.push(Frame::new(image.into_rgba8()));
+
+        if !decoder.more_images() {
+            break;
+        }
+        decoder.next_image()?;
+    }
+
+    Ok(frames)
+}
+
+#[cfg(any(not(feature = "gif"), not(feature = "png"), not(feature = "tiff")))]
+fn missing_feature(path: &Path, feature: &str) -> Result<Vec<Frame>> {
+    anyhow::bail!("cannot decode {:?}: ascii_cli was built without the `{}` feature", path, feature)
+}
+
+/// Every file below the directory at `path`, recursively and in path order.
+pub fn files_in(path: &Path) -> Vec<PathBuf> {
+    let mut entries: Vec<PathBuf> = WalkDir::new(path)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .map(|entry| entry.path().to_path_buf())
+        .collect();
+    entries.sort();
+    entries
+}
+
+fn load_frames_from_directory(path: &Path) -> Result<Vec<Frame>> {
+    let entries = files_in(path);
+    if entries.is_empty() {
+        anyhow::bail!("no image files found in {:?}", path);
+    }
+
+    let mut frames = Vec::with_capacity(entries.len());
+    for entry in entries {
+        let image =
+            image::open(&entry).with_context(|| format!("failed to open image {:?}", entry))?;
+        frames.push(Frame::new(image.into_rgba8()));
+    }
+    Ok(frames)
+}
+
+#[cfg(all(test, feature = "tiff"))]
+mod tests {
+    use std::io::Cursor;
+
+    use tiff::encoder::{colortype, TiffEncoder};
+
+    use super::*;
+
+    #[test]
+    fn multi_page_tiff_frames() {
+        let mut data = Cursor::new(Vec::new());
+        let mut encoder = TiffEncoder::new(&mut data).unwrap();
+        encoder.write_image::<colortype::RGB8>(2, 2, &[0; 12]).unwrap();
+        encoder.write_image::<colortype::RGB8>(2, 2, &[255; 12]).unwrap();
+        data.set_position(0);
+
+        let frames = decode_tiff_pages(data).unwrap();
+
+        assert_eq!(2, frames.len());
+        assert_eq!([0, 0, 0, 255], frames[0].buffer().get_pixel(0, 0).0);
+        assert_eq!([255, 255, 255, 255], frames[1].buffer().get_pixel(1, 1).0);
+    }
+}
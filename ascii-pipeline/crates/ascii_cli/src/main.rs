@@ -1,14 +1,45 @@
 use std::fs::File;
-use std::io::Write;
+use std::io::{self, BufWriter, Read, Write};
+use std::num::NonZeroUsize;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::{env, iter, thread};
 
-use anyhow::{Context, Result};
-use ascii_render::{AsciiOptions, AsciiRenderer, EdgeMode, Gradient, LayoutPolicy};
+use anyhow::{ensure, Context, Result};
+use ascii_render::{
+    render_to_image, to_html, to_svg, AdaptiveThreshold, AnimationFrames, AsciiError, AsciiOptions,
+    AsciiRenderer, AutoLevels, Bilateral, BorderMode, ColorMode, Companding, Dither,
+    EdgeColorSource, EdgeKernel, EdgeMode, FontConfig, FrameSource, GlyphGrid, GlyphGridSeries,
+    Gradient, HtmlOptions, LayoutPolicy, LoopCount, PlayMode, Player, RenderMode, RenderOutput,
+    StartFrame, ToneMap, CGA, FONT_ASPECT_RANGE, GAME_BOY,
+};
 use clap::{Parser, Subcommand, ValueEnum};
-use image::codecs::gif::GifDecoder;
-use image::{AnimationDecoder, DynamicImage, Frame};
+use image::DynamicImage;
 use indicatif::{ProgressBar, ProgressStyle};
-use walkdir::WalkDir;
+use terminal_size::Width;
+
+mod cast;
+mod frames;
+mod pipeline;
+mod play;
+mod watch;
+
+use cast::CastWriter;
+use frames::{files_in, load_frames};
+use pipeline::render_ordered;
+use watch::watch_file;
+
+/// Clear the terminal and move the cursor to its top left corner.
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+/// Pixel width of a cell when rasterizing glyphs.
+const RASTER_CELL_WIDTH: u32 = 8;
+
+/// Escape sequence restoring the terminal's default colors.
+const RESET: &str = "\x1b[0m";
+
+/// Preview width used when the terminal size is unknown.
+const PREVIEW_WIDTH: u16 = 100;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about = "Convert images or animations to ASCII glyph grids")]
@@ -23,48 +54,145 @@ enum Commands {
     Preview(PreviewArgs),
     /// Convert an image to ASCII and write the result to disk
     Convert(ConvertArgs),
-    /// Convert an animation (GIF or directory of frames) to ASCII frame files
+    /// Convert an animation (GIF, APNG, multi-page TIFF or directory of frames) to ASCII frame
+    /// files
     Animate(AnimateArgs),
+    /// Convert every image in a directory to ASCII text files, mirroring its layout
+    Batch(BatchArgs),
+    /// Play an animation in the terminal: space pauses, left and right step through frames, `+`
+    /// and `-` change the speed, `r` reverses and `q` quits
+    Play(PlayArgs),
 }
 
 #[derive(Parser, Debug)]
 struct PreviewArgs {
-    /// Input image path
+    /// Input image path, or `-` to read the image from stdin
     input: PathBuf,
-    /// Target column width
-    #[arg(long, default_value_t = 100)]
-    width: u16,
+    /// Target column width [default: terminal width or 100, unless --height is given]
+    #[arg(long)]
+    width: Option<u16>,
+    /// Target row count; combined with --width, the image is fit into both
+    #[arg(long)]
+    height: Option<u16>,
+    /// Emit text, or rasterize the glyphs into a sixel or kitty graphics image
+    #[arg(long, value_enum, default_value = "text")]
+    format: OutputFormat,
+    /// Keep running and redraw the preview whenever the input file changes
+    #[arg(long, default_value_t = false)]
+    watch: bool,
     #[command(flatten)]
     settings: RenderSettings,
 }
 
 #[derive(Parser, Debug)]
 struct ConvertArgs {
-    /// Input image path
+    /// Input image path, or `-` to read the image from stdin
     input: PathBuf,
     /// Output file path
     #[arg(short, long)]
     output: PathBuf,
-    /// Target column width
-    #[arg(long, default_value_t = 120)]
-    width: u16,
+    /// Strip trailing whitespace from every row
+    #[arg(long, default_value_t = false)]
+    trim_trailing: bool,
+    /// Target column width [default: 120, unless --height is given]
+    #[arg(long)]
+    width: Option<u16>,
+    /// Target row count; combined with --width, the image is fit into both
+    #[arg(long)]
+    height: Option<u16>,
+    /// Emit text, or rasterize the glyphs into a sixel or kitty graphics image
+    #[arg(long, value_enum, default_value = "text")]
+    format: OutputFormat,
+    #[command(flatten)]
+    settings: RenderSettings,
+}
+
+#[derive(Parser, Debug)]
+struct BatchArgs {
+    /// Directory searched recursively for images
+    input: PathBuf,
+    /// Output directory; each image is written to its relative path with a `.txt` extension
+    #[arg(short, long)]
+    out_dir: PathBuf,
+    /// Number of images converted at once [default: available CPU cores]
+    #[arg(short, long)]
+    jobs: Option<NonZeroUsize>,
+    /// Strip trailing whitespace from every row
+    #[arg(long, default_value_t = false)]
+    trim_trailing: bool,
+    /// Target column width [default: 120, unless --height is given]
+    #[arg(long)]
+    width: Option<u16>,
+    /// Target row count; combined with --width, the image is fit into both
+    #[arg(long)]
+    height: Option<u16>,
     #[command(flatten)]
     settings: RenderSettings,
 }
 
 #[derive(Parser, Debug)]
 struct AnimateArgs {
-    /// Input animation path (GIF file or directory of images)
+    /// Input animation path (GIF, APNG or TIFF file, or directory of images)
     input: PathBuf,
     /// Output directory for frame files
     #[arg(short, long)]
     out_dir: PathBuf,
-    /// Target column width
-    #[arg(long, default_value_t = 120)]
-    width: u16,
-    /// Override frames per second when the input lacks timing information
+    /// Write one text file per frame, or a single asciicast recording
+    #[arg(long, value_enum, default_value = "frames")]
+    format: AnimationFormat,
+    /// Target column width [default: 120, unless --height is given]
+    #[arg(long)]
+    width: Option<u16>,
+    /// Target row count; combined with --width, the image is fit into both
+    #[arg(long)]
+    height: Option<u16>,
+    /// Also tile all frames into a single contact sheet, this many frames wide
+    #[arg(long, value_name = "COLUMNS")]
+    montage: Option<u16>,
+    /// Shrink larger input frames to fit WIDTHxHEIGHT pixels before rendering, which saves memory
+    /// and time on huge animations; around twice the output's columns and rows loses little detail
+    #[arg(long, value_name = "WIDTHxHEIGHT", value_parser = parse_size)]
+    max_frame_size: Option<(u32, u32)>,
+    /// Frames per second used when the input lacks timing information
+    #[arg(long, default_value_t = 12.0)]
+    fps: f32,
+    /// Ignore the input's frame timing and play every frame at --fps
+    #[arg(long, default_value_t = false)]
+    force_fps: bool,
+    /// Playback speed multiplier applied to every frame delay (0.5 = half speed)
+    #[arg(long, default_value_t = 1.0)]
+    speed: f32,
+    /// What playback does after the last frame; `pingpong` also writes the frames back to the
+    /// first one in reverse
+    #[arg(long, value_enum, default_value = "loop")]
+    loop_mode: LoopModeChoice,
+    #[command(flatten)]
+    settings: RenderSettings,
+}
+
+#[derive(Parser, Debug)]
+struct PlayArgs {
+    /// Input animation path (GIF, APNG or TIFF file, or directory of images)
+    input: PathBuf,
+    /// Target column width [default: terminal width or 100, unless --height is given]
+    #[arg(long)]
+    width: Option<u16>,
+    /// Target row count; combined with --width, the image is fit into both
+    #[arg(long)]
+    height: Option<u16>,
+    /// Frames per second used when the input lacks timing information
     #[arg(long, default_value_t = 12.0)]
     fps: f32,
+    /// Initial playback speed multiplier (0.5 = half speed)
+    #[arg(long, default_value_t = 1.0)]
+    speed: f32,
+    /// What playback does after the last frame; `once` pauses on it
+    #[arg(long, value_enum, default_value = "loop")]
+    loop_mode: LoopModeChoice,
+    /// Frame to start on: `first`, `random`, or a frame number counting from 1, as on the status
+    /// line
+    #[arg(long, value_name = "FRAME", default_value = "first", value_parser = parse_start_frame)]
+    start_frame: StartFrame,
     #[command(flatten)]
     settings: RenderSettings,
 }
@@ -74,24 +202,142 @@ struct RenderSettings {
     /// Gradient preset used to map intensity to glyphs
     #[arg(long, value_enum, default_value = "detailed")]
     gradient: GradientPreset,
+    /// Map intensity to just a dark and a light glyph, given as two characters like "# "
+    #[arg(long, value_name = "CHARS", value_parser = parse_two_tone, conflicts_with = "gradient")]
+    two_tone: Option<(char, char)>,
+    /// Custom glyph ramp from dark to light, like " .:-=+*#%@", overriding --gradient
+    #[arg(long, value_name = "CHARS", conflicts_with_all = ["gradient", "two_tone"])]
+    gradient_custom: Option<String>,
+    /// Reverse the glyph ramp chosen by the options above, so dark glyphs draw bright pixels.
+    /// Luminance is untouched: --invert flips it before the reversed ramp is applied, so with
+    /// both set glyphs are picked as for the original image while intensities stay inverted
+    #[arg(long, default_value_t = false)]
+    invert_gradient: bool,
     /// Brightness adjustment (-255..255)
     #[arg(long, default_value_t = 0.0)]
     brightness: f32,
+    /// Red channel offset (-255..255), for correcting color casts
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    brightness_r: f32,
+    /// Green channel offset (-255..255)
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    brightness_g: f32,
+    /// Blue channel offset (-255..255)
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    brightness_b: f32,
     /// Contrast adjustment (-255..255)
     #[arg(long, default_value_t = 0.0)]
     contrast: f32,
+    /// Stretch the 2nd to 98th percentile of the luminance to the full range before --contrast
+    /// and --brightness
+    #[arg(long, default_value_t = false)]
+    auto_levels: bool,
+    /// Gamma applied after contrast and brightness; above 1 brightens midtones, below 1 darkens
+    #[arg(long, default_value_t = 1.0, value_parser = parse_gamma)]
+    gamma: f32,
+    /// Compress high dynamic range luminance instead of clamping it
+    #[arg(long, value_enum, default_value = "none")]
+    tone_map: ToneMapChoice,
+    /// Reshape luminance logarithmically to lift shadows, or exponentially to deepen them
+    #[arg(long, value_enum, default_value = "none")]
+    compand: CompandChoice,
+    /// Mix neighboring glyphs to show intensities between them
+    #[arg(long, value_enum, default_value = "none")]
+    dither: DitherChoice,
+    /// Render only this source rectangle, given as `x,y,width,height` in pixels
+    #[arg(long, value_parser = parse_roi)]
+    roi: Option<(u32, u32, u32, u32)>,
+    /// Grayscale image whose dark areas hide the matching parts of the output
+    #[arg(long, value_name = "PATH")]
+    mask: Option<PathBuf>,
+    /// Median filter radius for removing noise (0 disables)
+    #[arg(long = "median", value_name = "N", default_value_t = 0)]
+    median_radius: u8,
+    /// Edge-preserving smoothing with this spatial sigma in cells, to reduce banding
+    #[arg(long, value_name = "SIGMA", value_parser = parse_sigma)]
+    bilateral: Option<f32>,
+    /// Luminance difference (0.0 - 1.0) that --bilateral still smooths across
+    #[arg(long, default_value_t = 0.1, value_parser = parse_sigma)]
+    bilateral_range: f32,
+    /// Gaussian blur with this sigma in cells before adjusting contrast, to calm noisy photos
+    #[arg(long = "blur", value_name = "SIGMA", default_value_t = 0.0)]
+    blur_sigma: f32,
+    /// Warm (positive) or cool (negative) the glyph colors, e.g. 1500 or -1500
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true)]
+    temperature: f32,
+    /// Snap glyph colors to a palette: `cga`, `gameboy` or a comma-separated list of `#rrggbb`
+    #[arg(long, value_parser = parse_palette)]
+    palette: Option<Palette>,
+    /// Binarize against the mean of a local window of this size, for uneven lighting
+    #[arg(long, value_name = "BLOCK")]
+    adaptive_threshold: Option<u16>,
+    /// Offset subtracted from the local mean by --adaptive-threshold
+    #[arg(long, default_value_t = 0.05)]
+    adaptive_c: f32,
     /// Invert luminance before processing
     #[arg(long, default_value_t = false)]
     invert: bool,
     /// Font aspect ratio (height / width)
     #[arg(long, default_value_t = 0.55)]
     font_aspect: f32,
+    /// Assume square character cells and keep the image's true proportions
+    #[arg(long, default_value_t = false)]
+    square_cells: bool,
     /// Edge detection strategy
     #[arg(long, value_enum, default_value = "none")]
     edge: EdgeChoice,
     /// Sobel edge threshold (0.0 - 1.0)
     #[arg(long, default_value_t = 0.2)]
     sobel_threshold: f32,
+    /// Draw every Sobel edge with this glyph instead of one chosen by edge strength
+    #[arg(long, value_name = "CHAR")]
+    edge_glyph: Option<char>,
+    /// Color of Sobel edges: `source`, `magnitude` or a `#rrggbb` color [default: source, or
+    /// magnitude with --grayscale]
+    #[arg(long, value_name = "COLOR", value_parser = parse_edge_color)]
+    edge_color: Option<EdgeColorSource>,
+    /// How Sobel edges treat the outermost cells: `zero` leaves them blank, `clamp` repeats and
+    /// `reflect` mirrors their neighbors
+    #[arg(long, value_enum, default_value = "clamp")]
+    edge_border: BorderChoice,
+    /// Thicken Sobel edges by this many cells on every side so thin lines stay visible
+    #[arg(long, value_name = "CELLS", default_value_t = 0)]
+    edge_dilate: u8,
+    /// How far (0.0 - 1.0) --edge overlay pulls edge cells toward the densest glyph
+    #[arg(long, default_value_t = 0.8)]
+    edge_strength: f32,
+    /// Edge strength (0.0 - 1.0) Canny edges need to extend a stronger edge
+    #[arg(long, default_value_t = 0.1)]
+    canny_low: f32,
+    /// Edge strength (0.0 - 1.0) at which Canny edges are always kept
+    #[arg(long, default_value_t = 0.3)]
+    canny_high: f32,
+    /// Draw two pixels per cell with `▀` for double vertical resolution, colored in truecolor
+    /// unless --color-mode says otherwise
+    #[arg(long, default_value_t = false)]
+    half_block: bool,
+    /// How cells are drawn: gradient glyphs, `▀` half blocks or 2x4-dot Braille glyphs
+    #[arg(long, value_enum, default_value = "glyphs", conflicts_with = "half_block")]
+    mode: ModeChoice,
+    /// Luminance (0.0 - 1.0) at which Braille dots are raised
+    #[arg(long, default_value_t = 0.5)]
+    braille_threshold: f32,
+    /// Color glyphs by their intensity instead of the source colors
+    #[arg(long, default_value_t = false)]
+    grayscale: bool,
+    /// Color each glyph with the average color of its source pixels, keeping thin colored lines
+    #[arg(long, default_value_t = false)]
+    area_color: bool,
+    /// Glyph drawn in transparent regions instead of the mapped glyph
+    #[arg(long, value_name = "CHAR")]
+    transparent_char: Option<char>,
+    /// Terminal color escapes emitted around glyphs [default: none, or truecolor with
+    /// --half-block]
+    #[arg(long, value_enum)]
+    color_mode: Option<ColorOutput>,
+    /// Color glyphs with exact 24-bit colors, short for --color-mode truecolor
+    #[arg(long, default_value_t = false, conflicts_with = "color_mode")]
+    color: bool,
 }
 
 #[derive(Copy, Clone, Debug, ValueEnum)]
@@ -102,10 +348,116 @@ enum GradientPreset {
     Binary,
 }
 
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum BorderChoice {
+    Zero,
+    Clamp,
+    Reflect,
+}
+
 #[derive(Copy, Clone, Debug, ValueEnum)]
 enum EdgeChoice {
     None,
     Sobel,
+    Prewitt,
+    Scharr,
+    /// Scharr edges drawn as `- / | \` glyphs following their direction
+    Oriented,
+    /// The usual shading with Sobel edges drawn over it in dense glyphs, see --edge-strength
+    Overlay,
+    /// One cell wide Canny edges, see --canny-low and --canny-high
+    Canny,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum ToneMapChoice {
+    None,
+    Reinhard,
+    Aces,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum CompandChoice {
+    None,
+    Log,
+    Exp,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum ModeChoice {
+    Glyphs,
+    HalfBlock,
+    Braille,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum DitherChoice {
+    None,
+    BlueNoise,
+    /// Tiled Bayer matrix, a regular pattern that stays in place between animation frames
+    Ordered,
+    /// Error diffusion, smoothest for still images but flickers between animation frames
+    #[value(alias = "floyd")]
+    FloydSteinberg,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum LoopModeChoice {
+    Loop,
+    #[value(name = "pingpong")]
+    PingPong,
+    Once,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+enum AnimationFormat {
+    /// A `frame_NNNN.txt` file per frame
+    Frames,
+    /// An asciicast v2 recording, playable with asciinema
+    Cast,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// Glyphs, colored according to --color-mode
+    Text,
+    /// A sixel image of the rendered glyphs
+    Sixel,
+    /// An image shown through the kitty graphics protocol
+    Kitty,
+    /// Per-cell luminance as comma-separated values, one line per row
+    Csv,
+    /// A standalone HTML page with the glyphs in their colors
+    Html,
+    /// An SVG image with one text element per glyph, sized by the assumed font aspect
+    Svg,
+    /// The grid, layout and every cell's glyph and colors as JSON
+    Json,
+    /// A PNG image of the glyphs drawn in their colors (convert only)
+    Png,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum ColorOutput {
+    /// Plain glyphs without color
+    None,
+    /// The 16 named ANSI colors
+    #[value(name = "16")]
+    Ansi16,
+    /// The xterm 256-color palette
+    #[value(name = "256")]
+    Ansi256,
+    /// Exact 24-bit colors
+    Truecolor,
+}
+
+/// Richest color escapes the terminal is likely to display correctly.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum TerminalColors {
+    Truecolor,
+    Ansi256,
+    Ansi16,
+    None,
 }
 
 fn main() -> Result<()> {
@@ -114,48 +466,116 @@ fn main() -> Result<()> {
         Commands::Preview(args) => preview(args),
         Commands::Convert(args) => convert(args),
         Commands::Animate(args) => animate(args),
+        Commands::Batch(args) => batch(args),
+        Commands::Play(args) => play(args),
     }
 }
 
 fn preview(args: PreviewArgs) -> Result<()> {
-    let renderer = AsciiRenderer::default();
-    let options = args.settings.to_options();
-    let layout = LayoutPolicy::FixedColumns(args.width);
-    let output = renderer
-        .render_path(&args.input, layout, options)
-        .with_context(|| format!("failed to render {:?}", args.input))?;
+    let support = terminal_color_support();
+    if matches!(args.settings.color_output(), ColorOutput::Truecolor)
+        && support != TerminalColors::Truecolor
+    {
+        let fallback = if support == TerminalColors::Ansi256 { "256" } else { "16" };
+        eprintln!(
+            "warning: terminal may not support 24-bit color, consider --color-mode {} instead",
+            fallback
+        );
+    }
+    ensure!(args.format != OutputFormat::Png, "--format png writes an image file, use convert");
+    if args.format == OutputFormat::Kitty && !kitty_detected() {
+        eprintln!(
+            "warning: terminal does not advertise kitty graphics support, output may be garbled"
+        );
+    }
+
+    ensure!(!args.watch || args.input != Path::new("-"), "--watch needs an input file, not stdin");
+
+    let mut options = args.settings.to_options()?;
+    options.keep_intensity = args.format == OutputFormat::Csv;
+    let default_width = preview_width(terminal_columns());
+    let layout = args.settings.layout(args.width, args.height, default_width);
+    let render = || -> Result<String> {
+        let output = render_input(&args.input, layout, options.clone())?;
+        Ok(args.format.format(&output, &args.settings))
+    };
 
-    for row in output.grid.rows() {
-        println!("{}", row);
+    if !args.watch {
+        println!("{}", render()?);
+        return Ok(());
     }
 
-    Ok(())
+    // Errors, like reading a file the editor is still writing, are shown until the next change.
+    watch_file(&args.input, || {
+        print!("{}", CLEAR_SCREEN);
+        match render() {
+            Ok(text) => println!("{}", text),
+            Err(err) => eprintln!("error: {:#}", err),
+        }
+    })
 }
 
 fn convert(args: ConvertArgs) -> Result<()> {
-    let renderer = AsciiRenderer::default();
-    let options = args.settings.to_options();
-    let layout = LayoutPolicy::FixedColumns(args.width);
-    let output = renderer
-        .render_path(&args.input, layout, options)
-        .with_context(|| format!("failed to render {:?}", args.input))?;
+    let mut options = args.settings.to_options()?;
+    options.keep_intensity = args.format == OutputFormat::Csv;
+    let layout = args.settings.layout(args.width, args.height, 120);
+    let output = render_input(&args.input, layout, options)?;
+
+    if args.format == OutputFormat::Png {
+        let image = render_to_image(&output.grid, &FontConfig::default());
+        return image
+            .save_with_format(&args.output, image::ImageFormat::Png)
+            .with_context(|| format!("failed to write {:?}", args.output));
+    }
 
     let mut file = File::create(&args.output)
         .with_context(|| format!("failed to create {:?}", args.output))?;
-    for row in output.grid.rows() {
-        writeln!(file, "{}", row)?;
+    let mut text = args.format.format(&output, &args.settings);
+    if args.trim_trailing {
+        text = trim_trailing(&text);
     }
+    writeln!(file, "{}", text)?;
     Ok(())
 }
 
+/// Render the image at `input`, or the one piped into stdin when `input` is `-`.
+fn render_input(input: &Path, layout: LayoutPolicy, options: AsciiOptions) -> Result<RenderOutput> {
+    if input == Path::new("-") {
+        return render_reader(io::stdin().lock(), layout, options);
+    }
+
+    AsciiRenderer
+        .render_path(input, layout, options)
+        .with_context(|| format!("failed to render {:?}", input))
+}
+
+/// Read an encoded image from `reader` to its end and render it, detecting the format from its
+/// contents.
+fn render_reader(
+    mut reader: impl Read,
+    layout: LayoutPolicy,
+    options: AsciiOptions,
+) -> Result<RenderOutput> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes).context("failed to read image from stdin")?;
+    ensure!(!bytes.is_empty(), "no image data on stdin");
+    ensure!(image::guess_format(&bytes).is_ok(), "stdin does not hold a recognized image format");
+
+    AsciiRenderer.render_bytes(&bytes, layout, options).context("failed to render stdin")
+}
+
 fn animate(args: AnimateArgs) -> Result<()> {
-    let renderer = AsciiRenderer::default();
-    let options = args.settings.to_options();
-    let layout = LayoutPolicy::FixedColumns(args.width);
+    let renderer = AsciiRenderer;
+    let options = args.settings.to_options()?;
+    let layout = args.settings.layout(args.width, args.height, 120);
     std::fs::create_dir_all(&args.out_dir)
         .with_context(|| format!("failed to create output directory {:?}", args.out_dir))?;
 
     let frames = load_frames(&args.input)?;
+    if frames.is_empty() {
+        return Err(AsciiError::NoFrames)
+            .with_context(|| format!("failed to animate {:?}", args.input));
+    }
     let progress = ProgressBar::new(frames.len() as u64);
     progress.set_style(
         ProgressStyle::with_template(
@@ -165,19 +585,91 @@ fn animate(args: AnimateArgs) -> Result<()> {
         .progress_chars("=> "),
     );
 
-    for (index, frame) in frames.into_iter().enumerate() {
-        let dynamic = DynamicImage::ImageRgba8(frame.into_buffer());
+    let mut cast = match args.format {
+        AnimationFormat::Frames => None,
+        AnimationFormat::Cast => {
+            let name = args.input.file_stem().unwrap_or(args.input.as_os_str());
+            let cast_path = args.out_dir.join(name).with_extension("cast");
+            let file = File::create(&cast_path)
+                .with_context(|| format!("failed to create {:?}", cast_path))?;
+            Some(CastWriter::new(BufWriter::new(file)))
+        },
+    };
+    // Ping-pong exports follow the frames with the ones between the last and the first in
+    // reverse, since neither frame files nor casts can play backwards.
+    let ping_pong = args.loop_mode.to_play_mode() == PlayMode::PingPong;
+    let source_count = frames.len();
+    let mut source = AnimationFrames::new(frames, LoopCount::Infinite);
+    if let Some((max_width, max_height)) = args.max_frame_size {
+        source = source.with_max_dimensions(max_width, max_height);
+    }
+    let echo_count = if ping_pong { source_count.saturating_sub(2) } else { 0 };
+    let frame_count = source_count + echo_count;
+    progress.set_length(frame_count as u64);
+
+    // Echoed frames are rendered again from their images in a second pass. Every image was
+    // decoded up front anyway, so keeping these needs no more memory than loading them did,
+    // unlike keeping their rendered text.
+    let mut echo = Vec::with_capacity(echo_count);
+    let mut index = 0;
+    let inputs = iter::from_fn(|| {
+        let image = source.next_frame()?;
+        let delay = source.frame_delay().unwrap_or_default();
+        if ping_pong && index > 0 && index + 1 < source_count {
+            echo.push((image.clone(), delay));
+        }
+        index += 1;
+        Some((image, delay))
+    });
+
+    let render = |index, (image, delay): (DynamicImage, Duration)| -> Result<_> {
+        let delay = args.frame_delay(delay);
         let output = renderer
-            .render_image(dynamic, layout, options.clone())
+            .render_image(image, layout, options.clone())
             .with_context(|| format!("failed to render frame {}", index))?;
-
-        let frame_path = args.out_dir.join(format!("frame_{:04}.txt", index));
-        let mut file = File::create(&frame_path)
-            .with_context(|| format!("failed to create {:?}", frame_path))?;
-        for row in output.grid.rows() {
-            writeln!(file, "{}", row)?;
+        let text = args.settings.color_output().format(&output.grid);
+        Ok((output.grid, text, delay))
+    };
+    let mut thumbnails = Vec::new();
+    let mut write = |index, (grid, text, delay): (GlyphGrid, String, Duration)| -> Result<()> {
+        match &mut cast {
+            Some(cast) => cast.write_frame(&text, grid.width, grid.height, delay)?,
+            None => GlyphGridSeries::write_frame(&args.out_dir, index, frame_count, &text)
+                .with_context(|| {
+                    format!("failed to write frame {} to {:?}", index, args.out_dir)
+                })?,
         }
         progress.inc(1);
+        if args.montage.is_some() && index < source_count {
+            thumbnails.push(grid);
+        }
+        Ok(())
+    };
+
+    // Frames render in parallel, but are written one at a time and in order.
+    let workers = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    render_ordered(inputs, workers, workers * 2, render, &mut write)?;
+    render_ordered(
+        echo.into_iter().rev(),
+        workers,
+        workers * 2,
+        |index, input| render(source_count + index, input),
+        |index, output| write(source_count + index, output),
+    )?;
+
+    if let Some(cast) = cast {
+        cast.into_inner().flush()?;
+    }
+
+    if let Some(columns) = args.montage {
+        let montage = GlyphGrid::montage(&thumbnails, columns, 1).context(
+            "--montage contact sheet is too large, use fewer columns or a smaller --width",
+        )?;
+        let name = args.input.file_stem().unwrap_or(args.input.as_os_str()).to_string_lossy();
+        let montage_path = args.out_dir.join(format!("{}_montage.txt", name));
+        let mut file = File::create(&montage_path)
+            .with_context(|| format!("failed to create {:?}", montage_path))?;
+        writeln!(file, "{}", args.settings.color_output().format(&montage))?;
     }
 
     progress
@@ -185,68 +677,335 @@ fn animate(args: AnimateArgs) -> Result<()> {
     Ok(())
 }
 
-fn load_frames(path: &Path) -> Result<Vec<Frame>> {
-    if path.is_dir() {
-        load_frames_from_directory(path)
-    } else {
-        let extension = path
-            .extension()
-            .and_then(|ext| ext.to_str())
-            .map(|ext| ext.to_ascii_lowercase())
-            .unwrap_or_default();
-        if extension == "gif" {
-            load_frames_from_gif(path)
+fn batch(args: BatchArgs) -> Result<()> {
+    ensure!(args.input.is_dir(), "{:?} is not a directory", args.input);
+    let options = args.settings.to_options()?;
+    let layout = args.settings.layout(args.width, args.height, 120);
+
+    // Files are picked by extension, so unrelated files next to the images are left alone.
+    let (images, skipped): (Vec<_>, Vec<_>) = files_in(&args.input)
+        .into_iter()
+        .partition(|path| image::ImageFormat::from_path(path).is_ok());
+    let progress = ProgressBar::new(images.len() as u64);
+    progress.set_style(
+        ProgressStyle::with_template(
+            "{spinner} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} images",
+        )
+        .unwrap()
+        .progress_chars("=> "),
+    );
+
+    // A file that fails to render doesn't stop the others, its error is reported at the end.
+    let workers = args.jobs.map_or_else(
+        || thread::available_parallelism().map_or(1, NonZeroUsize::get),
+        NonZeroUsize::get,
+    );
+    let mut failed = Vec::new();
+    render_ordered(
+        &images,
+        workers,
+        workers * 2,
+        |_, path| {
+            let rendered = AsciiRenderer
+                .render_path(path, layout, options.clone())
+                .with_context(|| format!("failed to render {:?}", path));
+            Ok(rendered.map(|output| args.settings.color_output().format(&output.grid)))
+        },
+        |index, text| {
+            let input = &images[index];
+            progress.inc(1);
+            let mut text = match text {
+                Ok(text) => text,
+                Err(err) => {
+                    progress.println(format!("warning: {:#}", err));
+                    failed.push(input);
+                    return Ok(());
+                },
+            };
+            if args.trim_trailing {
+                text = trim_trailing(&text);
+            }
+
+            let relative = input.strip_prefix(&args.input).unwrap_or(input);
+            let path = args.out_dir.join(relative).with_extension("txt");
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)
+                    .with_context(|| format!("failed to create output directory {:?}", parent))?;
+            }
+            let mut file =
+                File::create(&path).with_context(|| format!("failed to create {:?}", path))?;
+            writeln!(file, "{}", text)?;
+            Ok(())
+        },
+    )?;
+
+    progress.finish_with_message(format!(
+        "Converted {} images to {:?}, skipped {} other files",
+        images.len() - failed.len(),
+        args.out_dir,
+        skipped.len()
+    ));
+    ensure!(failed.is_empty(), "failed to convert {} of {} images", failed.len(), images.len());
+    Ok(())
+}
+
+fn play(args: PlayArgs) -> Result<()> {
+    let options = args.settings.to_options()?;
+    let default_width = preview_width(terminal_columns());
+    let layout = args.settings.layout(args.width, args.height, default_width);
+
+    let frames = load_frames(&args.input)?;
+    if frames.is_empty() {
+        return Err(AsciiError::NoFrames)
+            .with_context(|| format!("failed to play {:?}", args.input));
+    }
+
+    // Every frame is rendered up front, so stepping through them is instant.
+    let mut series = GlyphGridSeries::new(None);
+    series.play_mode = args.loop_mode.to_play_mode();
+    let mut texts = Vec::with_capacity(frames.len());
+    let workers = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+    render_ordered(
+        frames,
+        workers,
+        workers * 2,
+        |index, frame| {
+            let delay = args.frame_delay(frame.delay().into());
+            let dynamic = DynamicImage::ImageRgba8(frame.into_buffer());
+            let output = AsciiRenderer
+                .render_image(dynamic, layout, options.clone())
+                .with_context(|| format!("failed to render frame {}", index))?;
+            let text = args.settings.color_output().format(&output.grid);
+            Ok((output.grid, text, delay))
+        },
+        |_, (grid, text, delay)| {
+            series.push(grid, delay);
+            texts.push(text);
+            Ok(())
+        },
+    )?;
+
+    let (min_speed, max_speed) = Player::SPEED_RANGE;
+    let player = Player {
+        playback: series.start_playback(args.start_frame, &mut rand::thread_rng()),
+        speed: args.speed.clamp(min_speed, max_speed),
+        ..Player::default()
+    };
+    play::play(&series, &texts, player)
+}
+
+impl AnimateArgs {
+    /// Display time of a frame with the source `delay`, after --force-fps and --speed.
+    fn frame_delay(&self, delay: Duration) -> Duration {
+        let delay = if self.force_fps || delay.is_zero() {
+            Duration::from_secs_f64(1. / self.fps.max(0.01) as f64)
+        } else {
+            delay
+        };
+        delay.div_f64(self.speed.max(0.01) as f64)
+    }
+}
+
+impl PlayArgs {
+    /// Display time of a frame with the source `delay` at normal speed.
+    fn frame_delay(&self, delay: Duration) -> Duration {
+        if delay.is_zero() {
+            Duration::from_secs_f64(1. / self.fps.max(0.01) as f64)
         } else {
-            let image =
-                image::open(path).with_context(|| format!("failed to open image {:?}", path))?;
-            let frame = Frame::new(image.into_rgba8());
-            Ok(vec![frame])
+            delay
         }
     }
 }
 
-fn load_frames_from_gif(path: &Path) -> Result<Vec<Frame>> {
-    let file = File::open(path).with_context(|| format!("failed to open GIF {:?}", path))?;
-    let decoder =
-        GifDecoder::new(file).with_context(|| format!("failed to decode GIF {:?}", path))?;
-    let frames = decoder
-        .into_frames()
-        .collect_frames()
-        .with_context(|| format!("failed to collect frames from {:?}", path))?;
-    Ok(frames)
+impl RenderSettings {
+    /// Cell rendering selected by --mode or its --half-block shorthand.
+    fn render_mode(&self) -> RenderMode {
+        if self.half_block {
+            RenderMode::HalfBlock
+        } else {
+            self.mode.to_render_mode()
+        }
+    }
+
+    /// Color escapes selected by --color or --color-mode.
+    ///
+    /// Half blocks are meaningless without colors, so they default to truecolor.
+    fn color_output(&self) -> ColorOutput {
+        match self.color_mode {
+            _ if self.color => ColorOutput::Truecolor,
+            Some(color_mode) => color_mode,
+            None if self.render_mode() == RenderMode::HalfBlock => ColorOutput::Truecolor,
+            None => ColorOutput::None,
+        }
+    }
+
+    fn to_options(&self) -> Result<AsciiOptions> {
+        let mask = match &self.mask {
+            Some(path) => {
+                Some(image::open(path).with_context(|| format!("failed to open mask {:?}", path))?)
+            },
+            None => None,
+        };
+
+        let gradient = match (&self.gradient_custom, self.two_tone) {
+            (Some(chars), _) => {
+                Gradient::try_new(chars.as_str()).context("invalid --gradient-custom")?
+            },
+            (None, Some((dark, light))) => Gradient::two_tone(dark, light),
+            (None, None) => self.gradient.to_gradient(),
+        };
+        let gradient = if self.invert_gradient { gradient.reversed() } else { gradient };
+
+        Ok(AsciiOptions {
+            gradient,
+            brightness: self.brightness,
+            channel_brightness: [self.brightness_r, self.brightness_g, self.brightness_b],
+            contrast: self.contrast,
+            auto_levels: self.auto_levels.then(AutoLevels::default),
+            gamma: self.gamma,
+            tone_map: self.tone_map.to_tone_map(),
+            companding: self.compand.to_companding(),
+            roi: self.roi,
+            mask,
+            median_radius: self.median_radius,
+            bilateral: self.bilateral.map(|spatial_sigma| Bilateral {
+                spatial_sigma,
+                range_sigma: self.bilateral_range,
+            }),
+            blur_sigma: self.blur_sigma,
+            adaptive_threshold: self
+                .adaptive_threshold
+                .map(|block| AdaptiveThreshold { block, c: self.adaptive_c }),
+            invert: self.invert,
+            font_aspect: self.font_aspect,
+            square_cells: self.square_cells,
+            edge_mode: self.edge.to_mode(self),
+            dither: self.dither.to_dither(),
+            render_mode: self.render_mode(),
+            braille_threshold: self.braille_threshold,
+            color_mode: if self.grayscale { ColorMode::Luminance } else { ColorMode::ColorAlpha },
+            temperature: self.temperature,
+            area_color: self.area_color,
+            palette: self.palette.clone().map(|palette| palette.0),
+            transparent_char: self.transparent_char,
+            ..AsciiOptions::default()
+        })
+    }
+
+    /// Font aspect ratio the layout and rasterized cells are derived with.
+    fn cell_aspect(&self) -> f32 {
+        let (min, max) = FONT_ASPECT_RANGE;
+        if self.square_cells {
+            1.0
+        } else {
+            self.font_aspect.clamp(min, max)
+        }
+    }
+
+    /// Layout for the requested size, using `default_width` columns when neither is given.
+    fn layout(&self, width: Option<u16>, height: Option<u16>, default_width: u16) -> LayoutPolicy {
+        let cell_aspect = self.cell_aspect();
+        match (width, height) {
+            (Some(columns), Some(rows)) => LayoutPolicy::FitViewport { columns, rows, cell_aspect },
+            (None, Some(rows)) => {
+                LayoutPolicy::ScaleToHeight { rows, cell_aspect, max_columns: None }
+            },
+            (width, None) => LayoutPolicy::FixedColumns(width.unwrap_or(default_width)),
+        }
+    }
+
+    /// Pixel size of a rasterized cell, matching the assumed font aspect.
+    fn raster_cell_size(&self) -> (u32, u32) {
+        let height = (RASTER_CELL_WIDTH as f32 / self.cell_aspect()).round() as u32;
+        (RASTER_CELL_WIDTH, height)
+    }
 }
 
-fn load_frames_from_directory(path: &Path) -> Result<Vec<Frame>> {
-    let mut entries: Vec<PathBuf> = WalkDir::new(path)
-        .into_iter()
-        .filter_map(|entry| entry.ok())
-        .filter(|entry| entry.file_type().is_file())
-        .map(|entry| entry.path().to_path_buf())
-        .collect();
-    entries.sort();
-    if entries.is_empty() {
-        anyhow::bail!("no image files found in {:?}", path);
+fn parse_size(value: &str) -> Result<(u32, u32), String> {
+    let size = value.split_once('x').and_then(|(width, height)| {
+        Some((width.trim().parse::<u32>().ok()?, height.trim().parse::<u32>().ok()?))
+    });
+    match size {
+        Some((width, height)) if width > 0 && height > 0 => Ok((width, height)),
+        _ => Err(format!("invalid size {:?}, expected WIDTHxHEIGHT", value)),
+    }
+}
+
+fn parse_roi(value: &str) -> Result<(u32, u32, u32, u32), String> {
+    let parts = value
+        .split(',')
+        .map(|part| part.trim().parse::<u32>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| format!("invalid region {:?}: {}", value, err))?;
+    match parts[..] {
+        [x, y, width, height] => Ok((x, y, width, height)),
+        _ => Err(format!("invalid region {:?}, expected x,y,width,height", value)),
     }
+}
 
-    let mut frames = Vec::with_capacity(entries.len());
-    for entry in entries {
-        let image =
-            image::open(&entry).with_context(|| format!("failed to open image {:?}", entry))?;
-        frames.push(Frame::new(image.into_rgba8()));
+#[derive(Clone, Debug)]
+struct Palette(Vec<[u8; 3]>);
+
+fn parse_palette(value: &str) -> Result<Palette, String> {
+    let colors = match value.to_ascii_lowercase().as_str() {
+        "cga" => CGA.to_vec(),
+        "gameboy" => GAME_BOY.to_vec(),
+        _ => {
+            value.split(',').map(|color| parse_hex_color(color.trim())).collect::<Result<_, _>>()?
+        },
+    };
+    Ok(Palette(colors))
+}
+
+fn parse_gamma(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(gamma) if gamma > 0.0 && gamma.is_finite() => Ok(gamma),
+        _ => Err(format!("invalid gamma {:?}, expected a positive number", value)),
     }
-    Ok(frames)
 }
 
-impl RenderSettings {
-    fn to_options(&self) -> AsciiOptions {
-        let mut options = AsciiOptions::default();
-        options.gradient = self.gradient.to_gradient();
-        options.brightness = self.brightness;
-        options.contrast = self.contrast;
-        options.invert = self.invert;
-        options.font_aspect = self.font_aspect.max(0.1);
-        options.edge_mode = self.edge.to_mode(self);
-        options
+fn parse_sigma(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(sigma) if sigma > 0.0 && sigma.is_finite() => Ok(sigma),
+        _ => Err(format!("invalid sigma {:?}, expected a positive number", value)),
+    }
+}
+
+fn parse_start_frame(value: &str) -> Result<StartFrame, String> {
+    match value {
+        "first" => Ok(StartFrame::First),
+        "random" => Ok(StartFrame::Random),
+        _ => match value.parse::<usize>() {
+            Ok(frame) if frame > 0 => Ok(StartFrame::Index(frame - 1)),
+            _ => Err(format!("expected `first`, `random` or a frame number, got {:?}", value)),
+        },
+    }
+}
+
+fn parse_two_tone(value: &str) -> Result<(char, char), String> {
+    let mut chars = value.chars();
+    match (chars.next(), chars.next(), chars.next()) {
+        (Some(dark), Some(light), None) => Ok((dark, light)),
+        _ => Err(format!("expected a dark and a light character, got {:?}", value)),
+    }
+}
+
+fn parse_edge_color(value: &str) -> Result<EdgeColorSource, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "source" => Ok(EdgeColorSource::Source),
+        "magnitude" => Ok(EdgeColorSource::Magnitude),
+        _ => parse_hex_color(value).map(EdgeColorSource::Fixed),
+    }
+}
+
+fn parse_hex_color(value: &str) -> Result<[u8; 3], String> {
+    let hex = value.strip_prefix('#').unwrap_or(value);
+    let channel = |index: usize| {
+        hex.get(index..index + 2).and_then(|channel| u8::from_str_radix(channel, 16).ok())
+    };
+    match (hex.len(), channel(0), channel(2), channel(4)) {
+        (6, Some(r), Some(g), Some(b)) => Ok([r, g, b]),
+        _ => Err(format!("invalid color {:?}, expected #rrggbb", value)),
     }
 }
 
@@ -261,12 +1020,608 @@ impl GradientPreset {
     }
 }
 
+impl OutputFormat {
+    fn format(self, output: &RenderOutput, settings: &RenderSettings) -> String {
+        let grid = &output.grid;
+        match self {
+            OutputFormat::Text => settings.color_output().format(grid),
+            OutputFormat::Sixel => {
+                let (cell_width, cell_height) = settings.raster_cell_size();
+                grid.to_sixel(cell_width, cell_height)
+            },
+            OutputFormat::Kitty => {
+                let (cell_width, cell_height) = settings.raster_cell_size();
+                grid.to_kitty(cell_width, cell_height)
+            },
+            OutputFormat::Csv => output.intensities_to_csv().unwrap_or_default(),
+            OutputFormat::Html => to_html(grid, HtmlOptions::default()),
+            OutputFormat::Svg => {
+                let cell_width = RASTER_CELL_WIDTH as f32;
+                to_svg(grid, cell_width, cell_width / output.assumed_font_aspect)
+            },
+            OutputFormat::Json => {
+                serde_json::to_string_pretty(output).expect("render output serializes to JSON")
+            },
+            OutputFormat::Png => unreachable!("PNG output is written by convert, not as text"),
+        }
+    }
+}
+
+/// Columns of the terminal attached to stdout, if any.
+fn terminal_columns() -> Option<u16> {
+    terminal_size::terminal_size().map(|(Width(columns), _)| columns)
+}
+
+/// Default preview width, filling the terminal when its size is known.
+fn preview_width(terminal_columns: Option<u16>) -> u16 {
+    terminal_columns.filter(|&columns| columns > 0).unwrap_or(PREVIEW_WIDTH)
+}
+
+/// Remove trailing whitespace from each row, keeping interior spacing intact.
+fn trim_trailing(text: &str) -> String {
+    text.lines().map(str::trim_end).collect::<Vec<_>>().join("\n")
+}
+
+/// Whether the environment hints at a terminal implementing the kitty graphics protocol.
+fn kitty_detected() -> bool {
+    env::var_os("KITTY_WINDOW_ID").is_some()
+        || env::var("TERM").is_ok_and(|term| term.contains("kitty"))
+}
+
+/// Color support advertised by the `COLORTERM` and `TERM` environment variables.
+fn terminal_color_support() -> TerminalColors {
+    let colorterm = env::var("COLORTERM").ok();
+    let term = env::var("TERM").ok();
+    color_support_from(colorterm.as_deref(), term.as_deref())
+}
+
+fn color_support_from(colorterm: Option<&str>, term: Option<&str>) -> TerminalColors {
+    if matches!(colorterm, Some("truecolor" | "24bit")) {
+        return TerminalColors::Truecolor;
+    }
+
+    match term.unwrap_or_default() {
+        "" | "dumb" => TerminalColors::None,
+        term if term.ends_with("-direct") || term == "alacritty" || term.contains("kitty") => {
+            TerminalColors::Truecolor
+        },
+        term if term.contains("256color") => TerminalColors::Ansi256,
+        _ => TerminalColors::Ansi16,
+    }
+}
+
+impl ColorOutput {
+    /// Format all rows of `grid`, separated by newlines.
+    fn format(self, grid: &GlyphGrid) -> String {
+        match self {
+            ColorOutput::None => grid.rows().collect::<Vec<_>>().join("\n"),
+            ColorOutput::Ansi16 => with_reset(grid.to_ansi_16()),
+            ColorOutput::Ansi256 => with_reset(grid.to_ansi_256()),
+            ColorOutput::Truecolor => with_reset(grid.to_ansi_truecolor()),
+        }
+    }
+}
+
+/// Make sure colored `text` ends by resetting the terminal's colors, even when it has no rows.
+fn with_reset(mut text: String) -> String {
+    if !text.ends_with(RESET) {
+        text.push_str(RESET);
+    }
+    text
+}
+
+impl ModeChoice {
+    fn to_render_mode(self) -> RenderMode {
+        match self {
+            ModeChoice::Glyphs => RenderMode::Glyphs,
+            ModeChoice::HalfBlock => RenderMode::HalfBlock,
+            ModeChoice::Braille => RenderMode::Braille,
+        }
+    }
+}
+
+impl DitherChoice {
+    fn to_dither(self) -> Dither {
+        match self {
+            DitherChoice::None => Dither::None,
+            DitherChoice::BlueNoise => Dither::BlueNoise,
+            DitherChoice::Ordered => Dither::Ordered,
+            DitherChoice::FloydSteinberg => Dither::FloydSteinberg,
+        }
+    }
+}
+
+impl CompandChoice {
+    fn to_companding(self) -> Companding {
+        match self {
+            CompandChoice::None => Companding::None,
+            CompandChoice::Log => Companding::Log,
+            CompandChoice::Exp => Companding::Exp,
+        }
+    }
+}
+
+impl ToneMapChoice {
+    fn to_tone_map(self) -> ToneMap {
+        match self {
+            ToneMapChoice::None => ToneMap::None,
+            ToneMapChoice::Reinhard => ToneMap::Reinhard,
+            ToneMapChoice::Aces => ToneMap::Aces,
+        }
+    }
+}
+
+impl LoopModeChoice {
+    fn to_play_mode(self) -> PlayMode {
+        match self {
+            LoopModeChoice::Loop => PlayMode::Loop,
+            LoopModeChoice::PingPong => PlayMode::PingPong,
+            LoopModeChoice::Once => PlayMode::Once,
+        }
+    }
+}
+
+impl BorderChoice {
+    fn to_border_mode(self) -> BorderMode {
+        match self {
+            BorderChoice::Zero => BorderMode::Zero,
+            BorderChoice::Clamp => BorderMode::Clamp,
+            BorderChoice::Reflect => BorderMode::Reflect,
+        }
+    }
+}
 
 impl EdgeChoice {
     fn to_mode(self, settings: &RenderSettings) -> EdgeMode {
         match self {
             EdgeChoice::None => EdgeMode::None,
-            EdgeChoice::Sobel => EdgeMode::Sobel { threshold: settings.sobel_threshold },
+            EdgeChoice::Sobel | EdgeChoice::Prewitt | EdgeChoice::Scharr => EdgeMode::Sobel {
+                threshold: settings.sobel_threshold,
+                edge_glyph: settings.edge_glyph,
+                color: settings.edge_color.unwrap_or(if settings.grayscale {
+                    EdgeColorSource::Magnitude
+                } else {
+                    EdgeColorSource::Source
+                }),
+                border: settings.edge_border.to_border_mode(),
+                dilate: settings.edge_dilate,
+                kernel: self.to_kernel(),
+            },
+            EdgeChoice::Oriented => EdgeMode::SobelOriented {
+                threshold: settings.sobel_threshold,
+                border: settings.edge_border.to_border_mode(),
+                kernel: self.to_kernel(),
+            },
+            EdgeChoice::Overlay => EdgeMode::SobelOverlay {
+                threshold: settings.sobel_threshold,
+                strength: settings.edge_strength,
+            },
+            EdgeChoice::Canny => {
+                EdgeMode::Canny { low: settings.canny_low, high: settings.canny_high }
+            },
         }
     }
+
+    fn to_kernel(self) -> EdgeKernel {
+        match self {
+            EdgeChoice::None | EdgeChoice::Sobel | EdgeChoice::Overlay | EdgeChoice::Canny => {
+                EdgeKernel::Sobel
+            },
+            EdgeChoice::Prewitt => EdgeKernel::Prewitt,
+            // Scharr responds most evenly across directions, which keeps line glyphs consistent.
+            EdgeChoice::Scharr | EdgeChoice::Oriented => EdgeKernel::Scharr,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ascii_render::CellGlyph;
+
+    use super::*;
+
+    fn animate_args(input: &str, flags: &[&str]) -> AnimateArgs {
+        let args = ["ascii_cli", "animate", input];
+        let cli = Cli::try_parse_from(args.iter().chain(flags)).unwrap();
+        let Commands::Animate(args) = cli.command else { panic!("expected animate") };
+        args
+    }
+
+    #[test]
+    fn speed_scales_delays() {
+        let args = animate_args("in.gif", &["--out-dir", "out", "--speed", "2.0"]);
+        assert_eq!(Duration::from_millis(50), args.frame_delay(Duration::from_millis(100)));
+        assert_eq!(Duration::from_millis(150), args.frame_delay(Duration::from_millis(300)));
+
+        let args = animate_args("in.gif", &["--out-dir", "out", "--force-fps", "--fps", "10"]);
+        assert_eq!(Duration::from_millis(100), args.frame_delay(Duration::from_millis(300)));
+    }
+
+    #[cfg(feature = "gif")]
+    #[test]
+    fn gif_delays_reach_cast_export() {
+        use image::codecs::gif::GifEncoder;
+        use image::{Delay, Frame, Rgba, RgbaImage};
+
+        let dir = env::temp_dir().join(format!("ascii_cli_delays_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("delays.gif");
+        {
+            let mut encoder = GifEncoder::new(File::create(&input).unwrap());
+            for (shade, delay) in [(0, 50), (128, 120), (255, 300)] {
+                let buffer = RgbaImage::from_pixel(4, 4, Rgba([shade, shade, shade, 255]));
+                let delay = Delay::from_numer_denom_ms(delay, 1);
+                encoder.encode_frame(Frame::from_parts(buffer, 0, 0, delay)).unwrap();
+            }
+        }
+
+        let flags = ["--out-dir", dir.to_str().unwrap(), "--format", "cast", "--width", "4"];
+        animate(animate_args(input.to_str().unwrap(), &flags)).unwrap();
+
+        let cast = std::fs::read_to_string(dir.join("delays.cast")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let times: Vec<f64> = cast
+            .lines()
+            .skip(1)
+            .map(|line| {
+                serde_json::from_str::<serde_json::Value>(line).unwrap()[0].as_f64().unwrap()
+            })
+            .collect();
+        assert_eq!(vec![0.0, 0.05, 0.17], times);
+    }
+
+    #[cfg(feature = "gif")]
+    #[test]
+    fn max_frame_size_keeps_output() {
+        use image::codecs::gif::GifEncoder;
+        use image::{Delay, Frame, Rgba, RgbaImage};
+
+        let dir = env::temp_dir().join(format!("ascii_cli_max_size_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("large.gif");
+        {
+            let mut encoder = GifEncoder::new(File::create(&input).unwrap());
+            for (shade, delay) in [(0, 50), (128, 120), (255, 300)] {
+                let buffer = RgbaImage::from_pixel(64, 32, Rgba([shade, 0, 0, 255]));
+                let delay = Delay::from_numer_denom_ms(delay, 1);
+                encoder.encode_frame(Frame::from_parts(buffer, 0, 0, delay)).unwrap();
+            }
+        }
+
+        let cast = |max_frame_size: &[&str]| {
+            let mut flags = vec!["--out-dir", dir.to_str().unwrap(), "--format", "cast"];
+            flags.extend(["--width", "4", "--color"]);
+            flags.extend(max_frame_size);
+            animate(animate_args(input.to_str().unwrap(), &flags)).unwrap();
+            std::fs::read_to_string(dir.join("large.cast")).unwrap()
+        };
+        let full = cast(&[]);
+        let capped = cast(&["--max-frame-size", "8x8"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(full, capped);
+        assert_eq!(4, capped.lines().count());
+
+        assert_eq!(Ok((8, 6)), parse_size("8x6"));
+        assert!(parse_size("8").is_err());
+        assert!(parse_size("0x6").is_err());
+    }
+
+    #[cfg(feature = "gif")]
+    #[test]
+    fn ping_pong_cast_export() {
+        use image::codecs::gif::GifEncoder;
+        use image::{Delay, Frame, Rgba, RgbaImage};
+
+        let dir = env::temp_dir().join(format!("ascii_cli_ping_pong_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("bounce.gif");
+        {
+            let mut encoder = GifEncoder::new(File::create(&input).unwrap());
+            for shade in [0, 128, 255] {
+                let buffer = RgbaImage::from_pixel(4, 4, Rgba([shade, shade, shade, 255]));
+                let delay = Delay::from_numer_denom_ms(100, 1);
+                encoder.encode_frame(Frame::from_parts(buffer, 0, 0, delay)).unwrap();
+            }
+        }
+
+        let mut flags = vec!["--out-dir", dir.to_str().unwrap(), "--format", "cast"];
+        flags.extend(["--width", "4", "--loop-mode", "pingpong"]);
+        animate(animate_args(input.to_str().unwrap(), &flags)).unwrap();
+
+        let cast = std::fs::read_to_string(dir.join("bounce.cast")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        let frames: Vec<String> = cast
+            .lines()
+            .skip(1)
+            .map(|line| {
+                let event = serde_json::from_str::<serde_json::Value>(line).unwrap();
+                event[2].as_str().unwrap().to_owned()
+            })
+            .collect();
+        assert_eq!(4, frames.len());
+        assert_eq!(frames[1], frames[3]);
+        assert_ne!(frames[0], frames[1]);
+        assert_ne!(frames[2], frames[3]);
+
+        let once = animate_args("in.gif", &["--out-dir", "out", "--loop-mode", "once"]);
+        assert_eq!(PlayMode::Once, once.loop_mode.to_play_mode());
+    }
+
+    #[test]
+    fn play_loop_mode() {
+        let shown = |loop_mode: &str| {
+            let cli =
+                Cli::try_parse_from(["ascii_cli", "play", "in.gif", "--loop-mode", loop_mode]);
+            let Commands::Play(args) = cli.unwrap().command else { panic!("expected play") };
+            let mut series = GlyphGridSeries::new(None);
+            series.play_mode = args.loop_mode.to_play_mode();
+            for ch in ['a', 'b', 'c'] {
+                series.push(GlyphGrid::new(1, 1, vec![CellGlyph::new(ch, 1.)]), Duration::ZERO);
+            }
+
+            let mut player = Player::default();
+            let mut shown = vec![player.playback.index];
+            for _ in 0..5 {
+                player.tick(&series);
+                shown.push(player.playback.index);
+            }
+            (shown, player.paused)
+        };
+
+        assert_eq!((vec![0, 1, 2, 2, 2, 2], true), shown("once"));
+        assert_eq!((vec![0, 1, 2, 1, 0, 1], false), shown("pingpong"));
+        assert_eq!((vec![0, 1, 2, 0, 1, 2], false), shown("loop"));
+    }
+
+    #[test]
+    fn start_frame_flag() {
+        let start_frame = |value: &str| {
+            let cli = Cli::try_parse_from(["ascii_cli", "play", "in.gif", "--start-frame", value])?;
+            let Commands::Play(args) = cli.command else { panic!("expected play") };
+            Ok::<_, clap::Error>(args.start_frame)
+        };
+
+        assert_eq!(StartFrame::Index(3), start_frame("4").unwrap());
+        assert_eq!(StartFrame::Random, start_frame("random").unwrap());
+        assert_eq!(StartFrame::First, start_frame("first").unwrap());
+        assert!(start_frame("0").is_err());
+        assert!(start_frame("last").is_err());
+
+        let cli = Cli::try_parse_from(["ascii_cli", "play", "in.gif"]).unwrap();
+        let Commands::Play(args) = cli.command else { panic!("expected play") };
+        assert_eq!(StartFrame::First, args.start_frame);
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn batch_mirrors_directory() {
+        let dir = env::temp_dir().join(format!("ascii_cli_batch_{}", std::process::id()));
+        let (input, out_dir) = (dir.join("photos"), dir.join("ascii"));
+        std::fs::create_dir_all(input.join("nested")).unwrap();
+        for name in ["a.png", "nested/b.png"] {
+            DynamicImage::new_rgb8(4, 4).save(input.join(name)).unwrap();
+        }
+        std::fs::write(input.join("notes.md"), "not an image").unwrap();
+
+        let args = ["ascii_cli", "batch", input.to_str().unwrap(), "--out-dir"];
+        let cli = Cli::try_parse_from(args.iter().copied().chain([
+            out_dir.to_str().unwrap(),
+            "--jobs",
+            "2",
+            "--width",
+            "4",
+        ]));
+        let Commands::Batch(args) = cli.unwrap().command else { panic!("expected batch") };
+        batch(args).unwrap();
+
+        assert!(out_dir.join("a.txt").is_file());
+        assert!(out_dir.join("nested/b.txt").is_file());
+        assert!(!out_dir.join("notes.txt").exists());
+
+        // Broken images are reported without keeping the others from being converted.
+        std::fs::write(input.join("broken.png"), "not a png").unwrap();
+        std::fs::remove_dir_all(&out_dir).unwrap();
+        let cli = Cli::try_parse_from([
+            "ascii_cli",
+            "batch",
+            input.to_str().unwrap(),
+            "--out-dir",
+            out_dir.to_str().unwrap(),
+        ]);
+        let Commands::Batch(args) = cli.unwrap().command else { panic!("expected batch") };
+        let err = batch(args).unwrap_err();
+        assert_eq!("failed to convert 1 of 3 images", err.to_string());
+        assert!(out_dir.join("nested/b.txt").is_file());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn two_tone_flag() {
+        let cli = Cli::try_parse_from(["ascii_cli", "preview", "in.png", "--two-tone", "# "]);
+        let Commands::Preview(args) = cli.unwrap().command else { panic!("expected preview") };
+        let gradient = args.settings.to_options().unwrap().gradient;
+        assert_eq!(Gradient::two_tone('#', ' '), gradient);
+
+        assert!(Cli::try_parse_from(["ascii_cli", "preview", "in.png", "--two-tone", "#"]).is_err());
+    }
+
+    #[test]
+    fn gamma_flag() {
+        let preview = |gamma: &str| {
+            let cli = Cli::try_parse_from(["ascii_cli", "preview", "in.png", "--gamma", gamma])?;
+            let Commands::Preview(args) = cli.command else { panic!("expected preview") };
+            Ok::<_, clap::Error>(args.settings.to_options().unwrap().gamma)
+        };
+
+        assert_eq!(2.2, preview("2.2").unwrap());
+        assert!(preview("0").is_err());
+        assert!(preview("-1").is_err());
+    }
+
+    #[test]
+    fn bilateral_flags() {
+        let preview = |flags: &[&str]| {
+            let args = ["ascii_cli", "preview", "in.png"].iter().chain(flags);
+            let cli = Cli::try_parse_from(args)?;
+            let Commands::Preview(args) = cli.command else { panic!("expected preview") };
+            Ok::<_, clap::Error>(args.settings.to_options().unwrap().bilateral)
+        };
+
+        let bilateral = preview(&["--bilateral", "1.5", "--bilateral-range", "0.2"]).unwrap();
+        assert_eq!(Some(Bilateral { spatial_sigma: 1.5, range_sigma: 0.2 }), bilateral);
+        assert!(preview(&["--bilateral", "NaN"]).is_err());
+        assert!(preview(&["--bilateral", "0"]).is_err());
+        assert!(preview(&["--bilateral", "1", "--bilateral-range", "inf"]).is_err());
+    }
+
+    #[test]
+    fn gradient_custom_flag() {
+        let options = |command: &str, chars: &str| {
+            let mut args = vec!["ascii_cli", command, "in.gif", "--gradient-custom", chars];
+            if command == "convert" {
+                args.extend(["--output", "out.txt"]);
+            } else if command == "animate" || command == "batch" {
+                args.extend(["--out-dir", "out"]);
+            }
+            let settings = match Cli::try_parse_from(args).unwrap().command {
+                Commands::Preview(args) => args.settings,
+                Commands::Convert(args) => args.settings,
+                Commands::Animate(args) => args.settings,
+                Commands::Batch(args) => args.settings,
+                Commands::Play(args) => args.settings,
+            };
+            settings.to_options().map(|options| options.gradient)
+        };
+
+        for command in ["preview", "convert", "animate", "batch", "play"] {
+            assert_eq!(Gradient::new(" .:#"), options(command, " .:#").unwrap());
+        }
+        let err = options("preview", "#").unwrap_err();
+        let message = format!("{err:#}");
+        assert_eq!("invalid --gradient-custom: gradient needs at least two glyphs, got 1", message);
+    }
+
+    #[test]
+    fn invert_gradient_flag() {
+        let cli = Cli::try_parse_from([
+            "ascii_cli",
+            "preview",
+            "in.png",
+            "--gradient",
+            "standard",
+            "--invert-gradient",
+            "--invert",
+        ]);
+        let Commands::Preview(args) = cli.unwrap().command else { panic!("expected preview") };
+        let options = args.settings.to_options().unwrap();
+        assert_eq!(Gradient::standard().reversed(), options.gradient);
+        assert!(options.invert);
+    }
+
+    #[test]
+    fn braille_mode() {
+        let cli = Cli::try_parse_from([
+            "ascii_cli",
+            "preview",
+            "in.png",
+            "--mode",
+            "braille",
+            "--braille-threshold",
+            "0.3",
+        ]);
+        let Commands::Preview(args) = cli.unwrap().command else { panic!("expected preview") };
+        let options = args.settings.to_options().unwrap();
+        assert_eq!((RenderMode::Braille, 0.3), (options.render_mode, options.braille_threshold));
+
+        let both = ["ascii_cli", "preview", "in.png", "--mode", "braille", "--half-block"];
+        assert!(Cli::try_parse_from(both).is_err());
+    }
+
+    #[test]
+    fn height_selects_rows() {
+        let cli =
+            Cli::try_parse_from(["ascii_cli", "preview", "in.png", "--height", "40"]).unwrap();
+        let Commands::Preview(args) = cli.command else { panic!("expected preview") };
+
+        let layout = args.settings.layout(args.width, args.height, 100);
+        let geometry = layout.derive(256, 256, args.settings.cell_aspect()).unwrap();
+
+        assert_eq!(40, geometry.rows);
+        assert_eq!(73, geometry.columns);
+    }
+
+    #[test]
+    fn color_support_detection() {
+        assert_eq!(TerminalColors::Truecolor, color_support_from(Some("truecolor"), Some("xterm")));
+        assert_eq!(TerminalColors::Truecolor, color_support_from(Some("24bit"), None));
+        assert_eq!(TerminalColors::Truecolor, color_support_from(None, Some("alacritty")));
+        assert_eq!(TerminalColors::Truecolor, color_support_from(None, Some("xterm-kitty")));
+        assert_eq!(TerminalColors::Truecolor, color_support_from(None, Some("xterm-direct")));
+        assert_eq!(TerminalColors::Ansi256, color_support_from(None, Some("xterm-256color")));
+        assert_eq!(TerminalColors::Ansi256, color_support_from(Some(""), Some("screen-256color")));
+        assert_eq!(TerminalColors::Ansi16, color_support_from(None, Some("linux")));
+        assert_eq!(TerminalColors::None, color_support_from(None, Some("dumb")));
+        assert_eq!(TerminalColors::None, color_support_from(None, None));
+    }
+
+    #[test]
+    fn preview_width_falls_back() {
+        assert_eq!(PREVIEW_WIDTH, preview_width(None));
+        assert_eq!(PREVIEW_WIDTH, preview_width(Some(0)));
+        assert_eq!(180, preview_width(Some(180)));
+    }
+
+    #[test]
+    fn color_flag_selects_truecolor() {
+        let settings = |flags: &[&str]| {
+            let args = ["ascii_cli", "convert", "in.png", "--output", "out.txt"];
+            let cli = Cli::try_parse_from(args.iter().chain(flags))?;
+            let Commands::Convert(args) = cli.command else { panic!("expected convert") };
+            Ok::<_, clap::Error>(args.settings)
+        };
+
+        assert!(matches!(settings(&["--color"]).unwrap().color_output(), ColorOutput::Truecolor));
+        assert!(matches!(settings(&[]).unwrap().color_output(), ColorOutput::None));
+        let half_block = settings(&["--half-block"]).unwrap();
+        assert!(matches!(half_block.color_output(), ColorOutput::Truecolor));
+        let half_block_16 = settings(&["--half-block", "--color-mode", "16"]).unwrap();
+        assert!(matches!(half_block_16.color_output(), ColorOutput::Ansi16));
+        let mode = settings(&["--mode", "half-block"]).unwrap();
+        assert!(matches!(mode.color_output(), ColorOutput::Truecolor));
+        assert!(settings(&["--color", "--color-mode", "16"]).is_err());
+    }
+
+    #[test]
+    fn colored_output_ends_with_reset() {
+        let grid = GlyphGrid::new(2, 1, vec![CellGlyph::new('#', 1.); 2]);
+        for color_mode in [ColorOutput::Ansi16, ColorOutput::Ansi256, ColorOutput::Truecolor] {
+            let text = color_mode.format(&grid);
+            assert!(text.ends_with(RESET) && !text.ends_with("\x1b[0m\x1b[0m"), "{text:?}");
+            assert_eq!(RESET, color_mode.format(&GlyphGrid::blank(0, 0)));
+        }
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn render_piped_image() {
+        let mut png = Vec::new();
+        DynamicImage::new_rgb8(4, 2)
+            .write_to(&mut io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        let layout = LayoutPolicy::FixedColumns(4);
+
+        let output = render_reader(png.as_slice(), layout, AsciiOptions::default()).unwrap();
+        assert_eq!(4, output.grid.width);
+
+        let empty = render_reader(io::empty(), layout, AsciiOptions::default()).unwrap_err();
+        assert_eq!("no image data on stdin", empty.to_string());
+        let text = render_reader(&b"not an image"[..], layout, AsciiOptions::default());
+        assert_eq!("stdin does not hold a recognized image format", text.unwrap_err().to_string());
+    }
+
+    #[test]
+    fn trim_trailing_keeps_interior_spaces() {
+        assert_eq!("a  b\n\n  c", trim_trailing("a  b   \n    \n  c "));
+    }
 }
@@ -0,0 +1,165 @@
+//! Parallel rendering with ordered, bounded-memory output.
+
+use std::collections::BTreeMap;
+use std::sync::{mpsc, Mutex};
+use std::thread;
+
+use anyhow::Result;
+
+/// Render `inputs` on `workers` threads and pass each result to `write` in input order.
+///
+/// No more than `window` inputs are handed out before their results are written, so the number
+/// of results alive at once stays bounded no matter how many inputs there are.
+pub fn render_ordered<I, T, O, R, W>(
+    inputs: I,
+    workers: usize,
+    window: usize,
+    render: R,
+    mut write: W,
+) -> Result<()>
+where
+    I: IntoIterator<Item = T>,
+    T: Send,
+    O: Send,
+    R: Fn(usize, T) -> Result<O> + Sync,
+    W: FnMut(usize, O) -> Result<()>,
+{
+    let mut inputs = inputs.into_iter().enumerate();
+    let (job_tx, job_rx) = mpsc::channel::<(usize, T)>();
+    let (result_tx, result_rx) = mpsc::channel::<(usize, Result<O>)>();
+    let job_rx = Mutex::new(job_rx);
+
+    thread::scope(|scope| {
+        for _ in 0..workers.max(1) {
+            let result_tx = result_tx.clone();
+            let (job_rx, render) = (&job_rx, &render);
+            scope.spawn(move || loop {
+                // Release the lock before rendering so other workers can pick up jobs.
+                let job = job_rx.lock().unwrap().recv();
+                let Ok((index, input)) = job else { break };
+                if result_tx.send((index, render(index, input))).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(result_tx);
+
+        let mut job_tx = Some(job_tx);
+        let mut in_flight = 0;
+        let mut next_job = |job_tx: &mut Option<mpsc::Sender<(usize, T)>>| {
+            match (inputs.next(), job_tx.as_ref()) {
+                (Some(job), Some(sender)) => sender.send(job).is_ok(),
+                // Closing the channel lets idle workers exit.
+                _ => {
+                    job_tx.take();
+                    false
+                },
+            }
+        };
+
+        for _ in 0..window.max(1) {
+            if !next_job(&mut job_tx) {
+                break;
+            }
+            in_flight += 1;
+        }
+
+        let mut pending = BTreeMap::new();
+        let mut next_index = 0;
+        while in_flight > 0 {
+            let Ok((index, result)) = result_rx.recv() else { break };
+            pending.insert(index, result);
+
+            while let Some(result) = pending.remove(&next_index) {
+                let index = next_index;
+                in_flight -= 1;
+                next_index += 1;
+
+                let written = result.and_then(|output| write(index, output));
+                if let Err(err) = written {
+                    job_tx.take();
+                    return Err(err);
+                }
+
+                if next_job(&mut job_tx) {
+                    in_flight += 1;
+                }
+            }
+        }
+
+        job_tx.take();
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+
+    /// Stand-in for a rendered frame that tracks how many are alive.
+    struct Output<'a> {
+        index: usize,
+        live: &'a AtomicUsize,
+    }
+
+    impl<'a> Output<'a> {
+        fn new(index: usize, live: &'a AtomicUsize, peak: &AtomicUsize) -> Self {
+            let count = live.fetch_add(1, Ordering::SeqCst) + 1;
+            peak.fetch_max(count, Ordering::SeqCst);
+            Self { index, live }
+        }
+    }
+
+    impl Drop for Output<'_> {
+        fn drop(&mut self) {
+            self.live.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn ordered_and_bounded() {
+        let (live, peak) = (AtomicUsize::new(0), AtomicUsize::new(0));
+        let mut written = Vec::new();
+
+        render_ordered(
+            0..100,
+            4,
+            8,
+            |index, input| {
+                // Finish out of order so results have to wait for their predecessors.
+                thread::sleep(std::time::Duration::from_micros((100 - input as u64) % 7 * 50));
+                Ok(Output::new(index, &live, &peak))
+            },
+            |index, output| {
+                assert_eq!(index, output.index);
+                written.push(index);
+                Ok(())
+            },
+        )
+        .unwrap();
+
+        assert_eq!((0..100).collect::<Vec<_>>(), written);
+        assert!(peak.load(Ordering::SeqCst) <= 8, "peak {}", peak.load(Ordering::SeqCst));
+        assert_eq!(0, live.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn render_errors_stop_the_pipeline() {
+        let mut written = 0;
+        let result = render_ordered(
+            0..100,
+            2,
+            4,
+            |_, input: usize| if input == 10 { Err(anyhow::anyhow!("bad frame")) } else { Ok(()) },
+            |_, ()| {
+                written += 1;
+                Ok(())
+            },
+        );
+
+        assert_eq!("bad frame", result.unwrap_err().to_string());
+        assert_eq!(10, written);
+    }
+}
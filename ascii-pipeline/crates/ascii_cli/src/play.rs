@@ -0,0 +1,191 @@
+//! Interactive playback of rendered frames in the terminal.
+
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use ascii_render::{GlyphGridSeries, Player, PlayerControl, DEFAULT_FRAME_DELAY};
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Print, ResetColor};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+
+/// Longest wait for input before checking whether a signal asked the viewer to stop.
+const SIGNAL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Set by the signal handler when the process is asked to stop.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// What a key press does in the viewer.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Action {
+    Control(PlayerControl),
+    Quit,
+}
+
+/// Raw mode and the alternate screen with a hidden cursor, for as long as the viewer runs.
+///
+/// The terminal is restored on drop, which also happens while unwinding from a panic, so it isn't
+/// left unusable.
+struct TerminalSession;
+
+impl TerminalSession {
+    fn start() -> Result<Self> {
+        terminal::enable_raw_mode().context("failed to enable raw terminal mode")?;
+        let session = Self;
+        execute!(io::stdout(), EnterAlternateScreen, Hide)?;
+        Ok(session)
+    }
+}
+
+impl Drop for TerminalSession {
+    fn drop(&mut self) {
+        // Nothing can be reported here, and failing to restore one part shouldn't skip the other.
+        let _ = restore(&mut io::stdout());
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Reset colors, clear the viewer's screen and bring back the cursor and the original screen.
+fn restore(out: &mut impl Write) -> io::Result<()> {
+    execute!(out, ResetColor, Clear(ClearType::All), Show, LeaveAlternateScreen)
+}
+
+/// Show `series` frame by frame until the user quits.
+///
+/// `texts` holds every frame already formatted for the terminal. The playback state is drawn on a
+/// status line below the frames.
+///
+/// Raw mode turns Ctrl-C into a key press, but signals sent by other processes, such as SIGINT,
+/// SIGTERM or SIGHUP, stop the viewer as well, restoring the terminal on the way out.
+pub fn play(series: &GlyphGridSeries, texts: &[String], mut player: Player) -> Result<()> {
+    ctrlc::set_handler(|| INTERRUPTED.store(true, Ordering::Relaxed))
+        .context("failed to install the signal handler")?;
+    let _session = TerminalSession::start()?;
+    let mut stdout = io::stdout().lock();
+    let mut shown_at = Instant::now();
+    let mut redraw = true;
+
+    while !INTERRUPTED.load(Ordering::Relaxed) {
+        if redraw {
+            draw(&mut stdout, series, texts, &player)?;
+            redraw = false;
+        }
+
+        // While paused, there's nothing to do until the next key press.
+        let deadline = (!player.paused).then(|| {
+            let delay = series.delay(player.playback.index).unwrap_or(DEFAULT_FRAME_DELAY);
+            shown_at + player.delay(delay)
+        });
+        let timeout = deadline.map_or(SIGNAL_POLL_INTERVAL, |deadline| {
+            deadline.saturating_duration_since(Instant::now()).min(SIGNAL_POLL_INTERVAL)
+        });
+        if !event::poll(timeout)? {
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                player.tick(series);
+                shown_at = Instant::now();
+                redraw = true;
+            }
+            continue;
+        }
+
+        match event::read()? {
+            Event::Key(key) => match action(key) {
+                Some(Action::Control(control)) => {
+                    player.apply(control, series.len());
+                    redraw = true;
+                },
+                Some(Action::Quit) => return Ok(()),
+                None => (),
+            },
+            Event::Resize(..) => {
+                queue!(stdout, Clear(ClearType::All))?;
+                redraw = true;
+            },
+            _ => (),
+        }
+    }
+
+    Ok(())
+}
+
+/// Draw the current frame with the status line below it.
+fn draw(
+    out: &mut impl Write,
+    series: &GlyphGridSeries,
+    texts: &[String],
+    player: &Player,
+) -> Result<()> {
+    queue!(out, MoveTo(0, 0))?;
+    // Raw mode doesn't return the cursor to the first column on line feeds.
+    for line in texts[player.playback.index].lines() {
+        queue!(out, Print(line), Print("\r\n"))?;
+    }
+
+    let height = series.frame(player.playback.index).map_or(0, |grid| grid.height);
+    queue!(
+        out,
+        MoveTo(0, height),
+        Clear(ClearType::CurrentLine),
+        Print(player.status_line(series.len()))
+    )?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Action bound to `key`, if any.
+fn action(key: KeyEvent) -> Option<Action> {
+    if key.kind == KeyEventKind::Release {
+        return None;
+    }
+
+    let control = match key.code {
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            return Some(Action::Quit)
+        },
+        KeyCode::Char('q') | KeyCode::Esc => return Some(Action::Quit),
+        KeyCode::Char(' ') => PlayerControl::TogglePause,
+        KeyCode::Right => PlayerControl::StepForward,
+        KeyCode::Left => PlayerControl::StepBackward,
+        KeyCode::Char('+' | '=') => PlayerControl::Faster,
+        KeyCode::Char('-') => PlayerControl::Slower,
+        KeyCode::Char('r') => PlayerControl::Reverse,
+        _ => return None,
+    };
+    Some(Action::Control(control))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn restore_resets_terminal() {
+        let mut out = Vec::new();
+        restore(&mut out).unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!("\x1b[0m\x1b[2J\x1b[?25h\x1b[?1049l", out);
+    }
+
+    #[test]
+    fn key_bindings() {
+        let press = |code| action(KeyEvent::new(code, KeyModifiers::NONE));
+
+        assert_eq!(Some(Action::Control(PlayerControl::TogglePause)), press(KeyCode::Char(' ')));
+        assert_eq!(Some(Action::Control(PlayerControl::StepBackward)), press(KeyCode::Left));
+        assert_eq!(Some(Action::Control(PlayerControl::Faster)), press(KeyCode::Char('+')));
+        assert_eq!(Some(Action::Quit), press(KeyCode::Char('q')));
+        assert_eq!(None, press(KeyCode::Char('x')));
+        assert_eq!(
+            Some(Action::Quit),
+            action(KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL))
+        );
+
+        let mut release = KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE);
+        release.kind = KeyEventKind::Release;
+        assert_eq!(None, action(release));
+    }
+}
@@ -0,0 +1,95 @@
+//! Re-rendering when the input file changes.
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use notify::event::{ModifyKind, RenameMode};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+/// Quiet time after the last change before rendering, so an editor can finish writing the file.
+const DEBOUNCE_DELAY: Duration = Duration::from_millis(100);
+
+/// Call `render` now and after every change to the file at `path`, until watching fails.
+///
+/// The file's directory is watched rather than the file itself, since editors often save by
+/// replacing the file, which would end a watch on the old one.
+pub fn watch_file(path: &Path, mut render: impl FnMut()) -> Result<()> {
+    let path = path.canonicalize().with_context(|| format!("failed to watch {:?}", path))?;
+    let directory = path.parent().map_or_else(|| PathBuf::from("/"), Path::to_path_buf);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = RecommendedWatcher::new(tx, notify::Config::default())
+        .with_context(|| format!("failed to watch {:?}", path))?;
+    watcher
+        .watch(&directory, RecursiveMode::NonRecursive)
+        .with_context(|| format!("failed to watch {:?}", directory))?;
+
+    render();
+    while let Some(events) = debounce(&rx, DEBOUNCE_DELAY) {
+        let changed = events
+            .into_iter()
+            .filter_map(|event| event.ok())
+            .filter(|event| is_change(&event.kind))
+            .flat_map(|event| event.paths)
+            .any(|changed| changed == path);
+        if changed {
+            render();
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether an event can leave the file with new contents.
+fn is_change(kind: &EventKind) -> bool {
+    match kind {
+        // Moving the file away is like deleting it, which has nothing new to render.
+        EventKind::Modify(ModifyKind::Name(RenameMode::From)) => false,
+        EventKind::Any | EventKind::Create(_) | EventKind::Modify(_) | EventKind::Other => true,
+        _ => false,
+    }
+}
+
+/// Wait for an event, then collect the events following it until none arrive for `delay`.
+///
+/// Returns `None` once the sender is gone and no events are left.
+fn debounce<T>(rx: &Receiver<T>, delay: Duration) -> Option<Vec<T>> {
+    let mut events = vec![rx.recv().ok()?];
+    let mut deadline = Instant::now() + delay;
+    loop {
+        match rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(event) => {
+                events.push(event);
+                deadline = Instant::now() + delay;
+            },
+            Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => return Some(events),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn debounce_groups_bursts() {
+        let (tx, rx) = mpsc::channel();
+        let sender = thread::spawn(move || {
+            for event in 0..3 {
+                tx.send(event).unwrap();
+                thread::sleep(Duration::from_millis(5));
+            }
+            thread::sleep(Duration::from_millis(200));
+            tx.send(3).unwrap();
+        });
+
+        assert_eq!(Some(vec![0, 1, 2]), debounce(&rx, Duration::from_millis(50)));
+        assert_eq!(Some(vec![3]), debounce(&rx, Duration::from_millis(50)));
+        sender.join().unwrap();
+        assert_eq!(None, debounce(&rx, Duration::from_millis(50)));
+    }
+}
@@ -0,0 +1,61 @@
+use ascii_render::bench::{extract_luma, sobel_map, synthetic_image};
+use ascii_render::{
+    AsciiOptions, AsciiRenderer, BorderMode, EdgeColorSource, EdgeKernel, EdgeMode, GlyphMapper,
+    LayoutPolicy, ToneMap,
+};
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+
+/// Grid sizes benchmarked for every stage, in cells.
+const SIZES: [(u16, u16); 3] = [(80, 40), (200, 100), (400, 200)];
+
+fn stages(c: &mut Criterion) {
+    let mut group = c.benchmark_group("stages");
+    for (width, height) in SIZES {
+        let id = format!("{width}x{height}");
+        let image = synthetic_image(width as u32, height as u32);
+        let luma = extract_luma(&image, false, ToneMap::None);
+
+        group.bench_with_input(BenchmarkId::new("extract_luma", &id), &image, |b, image| {
+            b.iter(|| extract_luma(image, false, ToneMap::None))
+        });
+        group.bench_with_input(BenchmarkId::new("sobel_map", &id), &luma, |b, luma| {
+            b.iter(|| sobel_map(luma, width, height, 0.2, BorderMode::Clamp))
+        });
+        group.bench_with_input(BenchmarkId::new("map_intensity", &id), &luma, |b, luma| {
+            let mut mapper = GlyphMapper::new(Default::default());
+            b.iter(|| mapper.map_intensity(luma, width, height))
+        });
+    }
+    group.finish();
+}
+
+fn render_image(c: &mut Criterion) {
+    let mut group = c.benchmark_group("render_image");
+    let image = synthetic_image(1600, 1600);
+    let renderer = AsciiRenderer;
+    for (width, height) in SIZES {
+        let options = AsciiOptions {
+            edge_mode: EdgeMode::Sobel {
+                threshold: 0.2,
+                edge_glyph: None,
+                color: EdgeColorSource::Source,
+                border: BorderMode::Clamp,
+                dilate: 0,
+                kernel: EdgeKernel::Sobel,
+            },
+            ..Default::default()
+        };
+        let layout = LayoutPolicy::FitViewport { columns: width, rows: height, cell_aspect: 0.5 };
+        group.bench_function(format!("{width}x{height}"), |b| {
+            b.iter_batched(
+                || (image.clone(), options.clone()),
+                |(image, options)| renderer.render_image(image, layout, options).unwrap(),
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, stages, render_image);
+criterion_main!(benches);
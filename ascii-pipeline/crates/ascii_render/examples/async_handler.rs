@@ -0,0 +1,21 @@
+//! Rendering from an async request handler without blocking the executor.
+//!
+//! Run with `cargo run -p ascii_render --example async_handler --features tokio -- image.png`.
+
+use std::path::PathBuf;
+
+use ascii_render::{AsciiError, AsciiOptions, AsciiRenderer, LayoutPolicy};
+
+/// A handler as it might appear in a web service, returning the rendered art as the body.
+async fn handle(path: PathBuf, columns: u16) -> Result<String, AsciiError> {
+    let layout = LayoutPolicy::FixedColumns(columns);
+    let output = AsciiRenderer.render_path_async(path, layout, AsciiOptions::default()).await?;
+    Ok(output.grid.rows().collect::<Vec<_>>().join("\n"))
+}
+
+#[tokio::main]
+async fn main() -> Result<(), AsciiError> {
+    let path = std::env::args().nth(1).expect("usage: async_handler <image>");
+    println!("{}", handle(path.into(), 80).await?);
+    Ok(())
+}
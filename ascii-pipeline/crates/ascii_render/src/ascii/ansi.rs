@@ -0,0 +1,276 @@
+use crate::image_pipeline::color::nearest_color;
+
+use super::grid::{CellGlyph, GlyphGrid};
+
+/// Default xterm values of the 16 named ANSI colors.
+pub const ANSI_16: [[u8; 3]; 16] = [
+    [0, 0, 0],
+    [205, 0, 0],
+    [0, 205, 0],
+    [205, 205, 0],
+    [0, 0, 238],
+    [205, 0, 205],
+    [0, 205, 205],
+    [229, 229, 229],
+    [127, 127, 127],
+    [255, 0, 0],
+    [0, 255, 0],
+    [255, 255, 0],
+    [92, 92, 255],
+    [255, 0, 255],
+    [0, 255, 255],
+    [255, 255, 255],
+];
+
+const RESET: &str = "\x1b[0m";
+
+/// Index of the named ANSI color closest to `color`.
+pub fn nearest_ansi_16(color: [u8; 3]) -> u8 {
+    nearest_color(color, &ANSI_16).unwrap_or(0) as u8
+}
+
+/// Channel values of the xterm 6x6x6 color cube at indices `16..232`.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Index of the xterm 256-color palette entry closest to `color`.
+///
+/// Only the 6x6x6 color cube and the grayscale ramp at `232..256` are considered, since the
+/// first 16 entries vary between terminals. Distances are Euclidean in RGB, with ties going to
+/// the cube.
+pub fn nearest_ansi_256(color: [u8; 3]) -> u8 {
+    let nearest_level = |value: u8| {
+        (0..CUBE_LEVELS.len()).min_by_key(|&index| CUBE_LEVELS[index].abs_diff(value)).unwrap_or(0)
+    };
+    let cube = color.map(nearest_level);
+    let cube_color = cube.map(|index| CUBE_LEVELS[index]);
+
+    // The closest gray is the ramp step closest to the mean of the channels.
+    let mean = color.iter().map(|&channel| u32::from(channel)).sum::<u32>() as f32 / 3.0;
+    let gray_step = ((mean - 8.0) / 10.0).round().clamp(0.0, 23.0) as u8;
+    let gray = 8 + 10 * gray_step;
+
+    let distance = |other: [u8; 3]| -> u32 {
+        color.iter().zip(other).map(|(&a, b)| u32::from(a.abs_diff(b)).pow(2)).sum()
+    };
+    if distance([gray; 3]) < distance(cube_color) {
+        232 + gray_step
+    } else {
+        16 + 36 * cube[0] as u8 + 6 * cube[1] as u8 + cube[2] as u8
+    }
+}
+
+impl GlyphGrid {
+    /// Render the grid with foreground colors snapped to the 16 named ANSI colors.
+    ///
+    /// Rows are separated by newlines and each row ends with a reset sequence. Transparent cells
+    /// are left uncolored.
+    pub fn to_ansi_16(&self) -> String {
+        let rows: Vec<String> = self
+            .ansi_rows(|cell| {
+                let fg = nearest_ansi_16(cell.fg);
+                let fg_code = if fg < 8 { 30 + fg } else { 90 + fg - 8 };
+                match cell.bg.map(nearest_ansi_16) {
+                    Some(bg) => {
+                        let bg_code = if bg < 8 { 40 + bg } else { 100 + bg - 8 };
+                        format!("\x1b[{};{}m", fg_code, bg_code)
+                    },
+                    None => format!("\x1b[{}m", fg_code),
+                }
+            })
+            .collect();
+        rows.join("\n")
+    }
+
+    /// Render the grid with colors snapped to the xterm 256-color palette.
+    ///
+    /// Rows are separated by newlines and each row ends with a reset sequence. Transparent cells
+    /// are left uncolored.
+    pub fn to_ansi_256(&self) -> String {
+        self.rows_ansi256().collect::<Vec<_>>().join("\n")
+    }
+
+    /// Rows of the grid with colors snapped to the xterm 256-color palette, see
+    /// [`nearest_ansi_256`].
+    ///
+    /// Each row ends with a reset sequence, and transparent cells are left uncolored.
+    pub fn rows_ansi256(&self) -> impl Iterator<Item = String> + '_ {
+        self.ansi_rows(|cell| {
+            let fg = nearest_ansi_256(cell.fg);
+            match cell.bg.map(nearest_ansi_256) {
+                Some(bg) => format!("\x1b[38;5;{};48;5;{}m", fg, bg),
+                None => format!("\x1b[38;5;{}m", fg),
+            }
+        })
+    }
+
+    /// Render the grid with exact 24-bit foreground and background colors.
+    ///
+    /// Rows are separated by newlines and each row ends with a reset sequence. Transparent cells
+    /// are left uncolored.
+    pub fn to_ansi_truecolor(&self) -> String {
+        self.rows_ansi().collect::<Vec<_>>().join("\n")
+    }
+
+    /// Rows of the grid with exact 24-bit colors, like [`GlyphGrid::rows`] but colored.
+    ///
+    /// Each row ends with a reset sequence, and transparent cells are left uncolored.
+    pub fn rows_ansi(&self) -> impl Iterator<Item = String> + '_ {
+        self.ansi_rows(|cell| {
+            let [r, g, b] = cell.fg;
+            match cell.bg {
+                Some([bg_r, bg_g, bg_b]) => {
+                    format!("\x1b[38;2;{};{};{};48;2;{};{};{}m", r, g, b, bg_r, bg_g, bg_b)
+                },
+                None => format!("\x1b[38;2;{};{};{}m", r, g, b),
+            }
+        })
+    }
+
+    /// Rows with every color change prefixed by the escape sequence returned from `escape`.
+    fn ansi_rows<F>(&self, escape: F) -> impl Iterator<Item = String> + '_
+    where
+        F: Fn(&CellGlyph) -> String + 'static,
+    {
+        self.cells.chunks(self.width.max(1) as usize).map(move |row| {
+            let mut line = String::new();
+            let mut current = None;
+            let mut background = false;
+            for cell in row {
+                // Transparent cells keep the terminal's default colors.
+                if cell.is_transparent() {
+                    if current.is_some() {
+                        line.push_str(RESET);
+                        current = None;
+                    }
+                    background = false;
+                    line.push(cell.ch);
+                    continue;
+                }
+
+                // Foreground-only sequences don't clear an earlier background.
+                if background && cell.bg.is_none() {
+                    line.push_str(RESET);
+                    current = None;
+                }
+                background = cell.bg.is_some();
+
+                let sequence = escape(cell);
+                if current.as_ref() != Some(&sequence) {
+                    line.push_str(&sequence);
+                    current = Some(sequence);
+                }
+                line.push(cell.ch);
+            }
+            line.push_str(RESET);
+            line
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn red_snaps_to_ansi_red() {
+        assert!(matches!(nearest_ansi_16([255, 0, 0]), 1 | 9));
+        assert_eq!(0, nearest_ansi_16([5, 5, 5]));
+        assert_eq!(15, nearest_ansi_16([250, 250, 250]));
+    }
+
+    #[test]
+    fn ansi_256_quantization() {
+        assert_eq!(16, nearest_ansi_256([0, 0, 0]));
+        assert_eq!(231, nearest_ansi_256([255, 255, 255]));
+        assert_eq!(196, nearest_ansi_256([255, 0, 0]));
+        assert_eq!(33, nearest_ansi_256([0, 135, 255]));
+        assert_eq!(59, nearest_ansi_256([95, 95, 95]));
+        assert_eq!(244, nearest_ansi_256([128, 128, 128]));
+        assert_eq!(233, nearest_ansi_256([18, 18, 18]));
+        assert_eq!(255, nearest_ansi_256([240, 238, 242]));
+    }
+
+    #[test]
+    fn ansi_256_rows() {
+        let mut cells = vec![CellGlyph::new('#', 1.); 3];
+        cells[0].fg = [255, 0, 0];
+        cells[1].fg = [250, 10, 10];
+        cells[2].bg = Some([0, 0, 0]);
+        let grid = GlyphGrid::new(3, 1, cells);
+
+        assert_eq!(
+            vec!["\x1b[38;5;196m##\x1b[38;5;231;48;5;16m#\x1b[0m"],
+            grid.rows_ansi256().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn ansi_16_rows() {
+        let mut cells = vec![CellGlyph::new('#', 0.); 4];
+        cells[0].fg = [255, 0, 0];
+        cells[1].fg = [250, 10, 10];
+        cells[2].fg = [0, 0, 0];
+        cells[3].fg = [255, 255, 255];
+        let grid = GlyphGrid::new(2, 2, cells);
+
+        assert_eq!("\x1b[91m##\x1b[0m\n\x1b[30m#\x1b[97m#\x1b[0m", grid.to_ansi_16());
+    }
+
+    #[test]
+    fn ansi_16_backgrounds() {
+        let mut cells = vec![CellGlyph::new('#', 1.); 3];
+        cells[0].bg = Some([0, 0, 0]);
+        cells[1].bg = Some([0, 0, 255]);
+        let grid = GlyphGrid::new(3, 1, cells);
+
+        assert_eq!("\x1b[97;40m#\x1b[97;44m#\x1b[0m\x1b[97m#\x1b[0m", grid.to_ansi_16());
+    }
+
+    #[test]
+    fn truecolor_rows() {
+        let mut cells = vec![CellGlyph::new('#', 1.); 3];
+        cells[0].fg = [250, 10, 10];
+        cells[1].fg = [250, 10, 10];
+        cells[2].bg = Some([0, 0, 128]);
+        let grid = GlyphGrid::new(3, 1, cells);
+
+        assert_eq!(
+            "\x1b[38;2;250;10;10m##\x1b[38;2;255;255;255;48;2;0;0;128m#\x1b[0m",
+            grid.to_ansi_truecolor()
+        );
+    }
+
+    #[test]
+    fn rows_ansi_reset_each_row() {
+        let mut cells = vec![CellGlyph::new('#', 1.); 4];
+        cells[0].fg = [1, 2, 3];
+        cells[3] = CellGlyph::blank();
+        let grid = GlyphGrid::new(2, 2, cells);
+
+        let rows: Vec<String> = grid.rows_ansi().collect();
+        assert_eq!(
+            vec![
+                "\x1b[38;2;1;2;3m#\x1b[38;2;255;255;255m#\x1b[0m",
+                "\x1b[38;2;255;255;255m#\x1b[0m \x1b[0m",
+            ],
+            rows
+        );
+    }
+
+    #[test]
+    fn transparent_cells_stay_uncolored() {
+        let mut cells = vec![CellGlyph::new('#', 1.); 3];
+        cells[0].bg = Some([0, 0, 128]);
+        cells[1] = CellGlyph::blank();
+        let grid = GlyphGrid::new(3, 1, cells);
+
+        assert_eq!("\x1b[97;44m#\x1b[0m \x1b[97m#\x1b[0m", grid.to_ansi_16());
+        assert_eq!(
+            "\x1b[38;2;255;255;255;48;2;0;0;128m#\x1b[0m \x1b[38;2;255;255;255m#\x1b[0m",
+            grid.to_ansi_truecolor()
+        );
+
+        let transparent = GlyphGrid::new(2, 1, vec![CellGlyph::blank(); 2]);
+        assert_eq!("  \x1b[0m", transparent.to_ansi_16());
+    }
+}
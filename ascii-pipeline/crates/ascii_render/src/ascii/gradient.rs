@@ -1,13 +1,77 @@
-#[derive(Clone, Debug, PartialEq, Eq)]
+/// Reason a gradient can't be built from a set of characters.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum GradientError {
+    #[error("gradient needs at least two glyphs, got {0}")]
+    TooFewGlyphs(usize),
+    #[error("gradient needs one non-negative weight per glyph, not all zero")]
+    InvalidWeights,
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Gradient {
     chars: Vec<char>,
+    /// Upper intensity bound of each character's bin, when the bins are not evenly spaced.
+    bounds: Option<Vec<f32>>,
 }
 
 impl Gradient {
+    /// Gradient of `chars` from dark to light.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `chars` has fewer than two characters; see [`Gradient::try_new`].
     pub fn new(chars: impl Into<String>) -> Self {
+        Self::try_new(chars).expect("gradient must contain at least two characters")
+    }
+
+    /// Gradient of `chars` from dark to light, or an error for fewer than two characters.
+    pub fn try_new(chars: impl Into<String>) -> Result<Self, GradientError> {
         let chars: Vec<char> = chars.into().chars().collect();
-        assert!(chars.len() >= 2, "gradient must contain at least two characters");
-        Self { chars }
+        if chars.len() < 2 {
+            return Err(GradientError::TooFewGlyphs(chars.len()));
+        }
+        Ok(Self { chars, bounds: None })
+    }
+
+    /// Give each character a share of the intensity range proportional to its weight.
+    ///
+    /// Lighter weights give narrower bins, so weighting the outer characters more heavily than
+    /// the middle ones dedicates more glyphs to midtones.
+    ///
+    /// Fails unless there's one finite, non-negative weight per character and at least one of them
+    /// is positive.
+    pub fn with_weights(mut self, weights: Vec<f32>) -> Result<Self, GradientError> {
+        let valid = weights.iter().all(|weight| weight.is_finite() && *weight >= 0.0);
+        let total: f32 = weights.iter().sum();
+        if weights.len() != self.chars.len() || !valid || total <= 0.0 {
+            return Err(GradientError::InvalidWeights);
+        }
+
+        let mut cumulative = 0.0;
+        let bounds = weights
+            .iter()
+            .map(|weight| {
+                cumulative += weight;
+                cumulative / total
+            })
+            .collect();
+        self.bounds = Some(bounds);
+        Ok(self)
+    }
+
+    /// The same gradient with its characters in reverse order, so the glyph for the darkest
+    /// intensity is used for the brightest and vice versa. Weighted bins are mirrored with their
+    /// characters.
+    pub fn reversed(&self) -> Self {
+        let chars = self.chars.iter().rev().copied().collect();
+        let bounds = self.bounds.as_ref().map(|bounds| {
+            // Bin `i` of the reversed gradient spans what bin `len - 1 - i` did, mirrored.
+            let mut reversed: Vec<f32> =
+                bounds.iter().rev().skip(1).map(|bound| 1.0 - bound).collect();
+            reversed.push(1.0);
+            reversed
+        });
+        Self { chars, bounds }
     }
 
     pub fn detailed() -> Self {
@@ -26,21 +90,169 @@ impl Gradient {
         Self::new("01")
     }
 
+    /// Gradient of just a `dark` glyph for low intensities and a `light` one for high ones.
+    pub fn two_tone(dark: char, light: char) -> Self {
+        Self::new([dark, light].iter().collect::<String>())
+    }
+
+    /// Preset with the given case-insensitive name, such as `"blocks"`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "detailed" => Some(Self::detailed()),
+            "standard" => Some(Self::standard()),
+            "blocks" => Some(Self::blocks()),
+            "binary" => Some(Self::binary()),
+            _ => None,
+        }
+    }
+
     pub fn len(&self) -> usize {
         self.chars.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.chars.is_empty()
+    }
+
     pub fn chars(&self) -> &[char] {
         &self.chars
     }
 
+    /// Index of the character for an intensity in `[0, 1]`.
+    ///
+    /// Without weights, intensities are spread linearly across the characters. A gradient with a
+    /// single character always yields index zero.
     pub fn clamp_index(&self, value: f32) -> usize {
-        let levels = (self.chars.len() - 1) as f32;
+        let max_index = self.chars.len().saturating_sub(1);
+        if let Some(bounds) = &self.bounds {
+            let value = value.clamp(0.0, 1.0);
+            let index = bounds.partition_point(|&bound| bound < value);
+            return index.min(max_index);
+        }
+
+        if max_index == 0 {
+            return 0;
+        }
+        let levels = max_index as f32;
         let idx = (value * levels).clamp(0.0, levels);
         idx.round() as usize
     }
 
+    /// Intensity the character at `index` stands for.
+    ///
+    /// That's the center of its bin for a weighted gradient, and its evenly spaced position from
+    /// `0.0` to `1.0` otherwise.
+    pub fn level(&self, index: usize) -> f32 {
+        let max_index = self.chars.len().saturating_sub(1);
+        let index = index.min(max_index);
+        match &self.bounds {
+            Some(bounds) => {
+                let lower = index.checked_sub(1).map_or(0.0, |below| bounds[below]);
+                (lower + bounds[index]) / 2.0
+            },
+            None if max_index == 0 => 0.0,
+            None => index as f32 / max_index as f32,
+        }
+    }
+
     pub fn char_at(&self, index: usize) -> char {
-        self.chars[index.min(self.chars.len() - 1)]
+        self.chars[index.min(self.chars.len().saturating_sub(1))]
+    }
+}
+
+impl Default for Gradient {
+    fn default() -> Self {
+        Self::detailed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ascii::mapping::GlyphMapper;
+
+    use super::*;
+
+    #[test]
+    fn preset_names() {
+        assert_eq!(Some(Gradient::blocks()), Gradient::from_name("Blocks"));
+        assert_eq!(None, Gradient::from_name("sparkles"));
+        assert_eq!(Gradient::detailed(), Gradient::from_name("sparkles").unwrap_or_default());
+    }
+
+    #[test]
+    fn try_new_counts_glyphs() {
+        assert_eq!(Ok(Gradient::binary()), Gradient::try_new("01"));
+        assert_eq!(Err(GradientError::TooFewGlyphs(0)), Gradient::try_new(""));
+        assert_eq!(Err(GradientError::TooFewGlyphs(1)), Gradient::try_new("█"));
+    }
+
+    #[test]
+    fn two_tone_order() {
+        let gradient = Gradient::two_tone('#', ' ');
+        assert_eq!(' ', gradient.char_at(gradient.clamp_index(1.)));
+        assert_eq!('#', gradient.char_at(gradient.clamp_index(0.)));
+        assert_eq!(Gradient::binary(), Gradient::two_tone('0', '1'));
+    }
+
+    #[test]
+    fn weights_favor_midtones() {
+        let linear = Gradient::standard();
+        let weighted = Gradient::standard()
+            .with_weights(vec![4., 4., 4., 1., 1., 1., 1., 4., 4., 4.])
+            .unwrap();
+
+        let distinct = |gradient: &Gradient| {
+            let mut indices: Vec<usize> =
+                (40..=60).map(|value| gradient.clamp_index(value as f32 / 100.)).collect();
+            indices.dedup();
+            indices.len()
+        };
+        assert!(distinct(&weighted) > distinct(&linear));
+
+        assert_eq!(0, weighted.clamp_index(0.));
+        assert_eq!(9, weighted.clamp_index(1.));
+        let uniform = Gradient::binary().with_weights(vec![1., 1.]).unwrap();
+        assert_eq!((0, 1), (uniform.clamp_index(0.49), uniform.clamp_index(0.51)));
+        assert_eq!((0.25, 0.75), (uniform.level(0), uniform.level(1)));
+        assert_eq!((0.0, 1.0), (Gradient::binary().level(0), Gradient::binary().level(1)));
+    }
+
+    #[test]
+    fn invalid_weights() {
+        for weights in [vec![1.], vec![1., -1.], vec![0., 0.], vec![1., f32::NAN], vec![]] {
+            let result = Gradient::binary().with_weights(weights.clone());
+            assert_eq!(Err(GradientError::InvalidWeights), result, "{weights:?}");
+        }
+    }
+
+    #[test]
+    fn reversed_order() {
+        let reversed = Gradient::standard().reversed();
+        assert_eq!(Gradient::new(" .:-=+*#%@"), reversed);
+        assert_eq!(Gradient::standard(), reversed.reversed());
+
+        let weighted = Gradient::new("abc").with_weights(vec![2., 1., 1.]).unwrap();
+        let reversed = weighted.reversed();
+        assert_eq!(&['c', 'b', 'a'], reversed.chars());
+        assert_eq!(Gradient::new("cba").with_weights(vec![1., 1., 2.]).unwrap(), reversed);
+        for value in [0., 0.2, 0.4, 0.6, 0.8, 1.] {
+            let original = weighted.char_at(weighted.clamp_index(1. - value));
+            assert_eq!(original, reversed.char_at(reversed.clamp_index(value)), "{value}");
+        }
+    }
+
+    #[test]
+    fn single_character_gradient() {
+        let gradient = Gradient { chars: vec!['#'], bounds: None };
+        for value in [0., 0.5, 1., f32::NAN] {
+            assert_eq!(0, gradient.clamp_index(value));
+        }
+
+        let mut mapper = GlyphMapper::new(gradient.clone());
+        let grid = mapper.map_intensity(&[0., 0.25, 0.75, 1.], 2, 2);
+        assert_eq!(vec!["##", "##"], grid.rows().collect::<Vec<_>>());
+
+        let mut mapper = GlyphMapper::new(gradient.with_weights(vec![1.]).unwrap());
+        assert!(mapper.map_intensity(&[0., 1.], 2, 1).cells.iter().all(|cell| cell.ch == '#'));
     }
 }
@@ -1,4 +1,7 @@
-#[derive(Clone, Debug)]
+use crate::{AsciiError, TRANSPARENT_ALPHA};
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CellGlyph {
     pub ch: char,
     /// Foreground color encoded as RGB bytes.
@@ -14,9 +17,43 @@ impl CellGlyph {
         let intensity = (intensity.clamp(0.0, 1.0) * 255.0).round() as u8;
         Self { ch, fg: [intensity; 3], bg: None, alpha: 1.0 }
     }
+
+    /// Fully transparent space, used to fill padding.
+    pub fn blank() -> Self {
+        Self { ch: ' ', fg: [0; 3], bg: None, alpha: 0.0 }
+    }
+
+    /// Whether the cell is transparent enough to let whatever is behind it show through.
+    pub fn is_transparent(&self) -> bool {
+        self.alpha < TRANSPARENT_ALPHA
+    }
+
+    /// Opacity of the cell's background color when painted over another background, zero for
+    /// cells without one.
+    pub fn background_alpha(&self) -> f32 {
+        self.bg.map_or(0.0, |_| self.alpha.clamp(0.0, 1.0))
+    }
+
+    /// Foreground color blended by the cell's alpha over `background`, such as the terminal's
+    /// background color.
+    pub fn foreground_over(&self, background: [u8; 3]) -> [u8; 3] {
+        blend(self.fg, background, self.alpha)
+    }
 }
 
-#[derive(Clone, Debug)]
+/// `color` painted with opacity `alpha` over `background`.
+pub(crate) fn blend(color: [u8; 3], background: [u8; 3], alpha: f32) -> [u8; 3] {
+    let alpha = alpha.clamp(0.0, 1.0);
+    let mix = |index: usize| {
+        let color = color[index] as f32;
+        let background = background[index] as f32;
+        (color * alpha + background * (1.0 - alpha)).round() as u8
+    };
+    [mix(0), mix(1), mix(2)]
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GlyphGrid {
     pub width: u16,
     pub height: u16,
@@ -29,8 +66,271 @@ impl GlyphGrid {
         Self { width, height, cells }
     }
 
+    /// Grid of `width`x`height` blank cells.
+    pub fn blank(width: u16, height: u16) -> Self {
+        Self::new(width, height, vec![CellGlyph::blank(); width as usize * height as usize])
+    }
+
+    /// Copy `other` into this grid with its top-left corner at `(x, y)`, clipping at the edges.
+    pub fn overlay(&mut self, other: &GlyphGrid, x: u16, y: u16) {
+        let columns = other.width.min(self.width.saturating_sub(x)) as usize;
+        let rows = other.height.min(self.height.saturating_sub(y)) as usize;
+        for row in 0..rows {
+            let source = row * other.width as usize;
+            let target = (y as usize + row) * self.width as usize + x as usize;
+            self.cells[target..target + columns]
+                .clone_from_slice(&other.cells[source..source + columns]);
+        }
+    }
+
+    /// Copy of the grid extended with blank cells to at least `width`x`height`.
+    pub fn pad_to(&self, width: u16, height: u16) -> Self {
+        let mut padded = Self::blank(width.max(self.width), height.max(self.height));
+        padded.overlay(self, 0, 0);
+        padded
+    }
+
+    /// Copy of the grid repeated horizontally and vertically to fill `width`x`height`.
+    ///
+    /// An empty grid yields blank cells.
+    pub fn tile(&self, width: u16, height: u16) -> Self {
+        self.tile_offset(width, height, 0, 0)
+    }
+
+    /// Like [`GlyphGrid::tile`], but with the pattern shifted so that source cell `(x, y)` lands
+    /// in the top-left corner.
+    pub fn tile_offset(&self, width: u16, height: u16, x: u16, y: u16) -> Self {
+        if self.width == 0 || self.height == 0 {
+            return Self::blank(width, height);
+        }
+
+        let (x, y) = (x as usize, y as usize);
+        let cells = (0..height as usize)
+            .flat_map(|line| {
+                let row = ((line + y) % self.height as usize) * self.width as usize;
+                (0..width as usize).map(move |column| row + (column + x) % self.width as usize)
+            })
+            .map(|index| self.cells[index].clone())
+            .collect();
+        Self::new(width, height, cells)
+    }
+
+    /// Copy of the grid scaled to `width`x`height` by taking every cell from the nearest source
+    /// cell.
+    pub fn resize_nearest(&self, width: u16, height: u16) -> Self {
+        if self.width == 0 || self.height == 0 {
+            return Self::blank(width, height);
+        }
+
+        // Sample at the center of each target cell.
+        let nearest = |target: usize, target_size: u16, source_size: u16| {
+            let position = (target as f32 + 0.5) * source_size as f32 / target_size as f32;
+            (position as usize).min(source_size as usize - 1)
+        };
+        let cells = (0..height as usize)
+            .flat_map(|line| {
+                let row = nearest(line, height, self.height) * self.width as usize;
+                (0..width as usize).map(move |column| row + nearest(column, width, self.width))
+            })
+            .map(|index| self.cells[index].clone())
+            .collect();
+        Self::new(width, height, cells)
+    }
+
+    /// Tile `grids` left to right, `columns` per row, separated by a `gutter` of blank cells.
+    ///
+    /// Every tile is padded to the size of the largest grid. Fails when the sheet would be wider
+    /// or taller than a grid can be.
+    pub fn montage(grids: &[GlyphGrid], columns: u16, gutter: u16) -> Result<Self, AsciiError> {
+        let columns = u64::from(columns).clamp(1, grids.len().max(1) as u64);
+        let rows = (grids.len() as u64).div_ceil(columns);
+        let tile_width = u64::from(grids.iter().map(|grid| grid.width).max().unwrap_or(0));
+        let tile_height = u64::from(grids.iter().map(|grid| grid.height).max().unwrap_or(0));
+        let gutter = u64::from(gutter);
+
+        // Widened so huge sheets are reported instead of overflowing.
+        let span = |count: u64, tile: u64| count * tile + count.saturating_sub(1) * gutter;
+        let (width, height) = (span(columns, tile_width), span(rows, tile_height));
+        let (Ok(width), Ok(height)) = (u16::try_from(width), u16::try_from(height)) else {
+            let clamp = |span: u64| span.min(u32::MAX as u64) as u32;
+            return Err(AsciiError::GridTooLarge(clamp(width), clamp(height)));
+        };
+
+        let mut montage = Self::blank(width, height);
+        for (index, grid) in grids.iter().enumerate() {
+            let (column, row) = (index as u64 % columns, index as u64 / columns);
+            // Offsets of placed tiles lie within the sheet, so they fit.
+            let x = column * (tile_width + gutter);
+            let y = row * (tile_height + gutter);
+            montage.overlay(grid, x as u16, y as u16);
+        }
+        Ok(montage)
+    }
+
+    /// Build a grid from rows of cells, which must all have the same width.
+    ///
+    /// Fails with [`AsciiError::GridTooLarge`] when there are more rows or columns than fit a grid.
+    pub fn from_2d(rows: Vec<Vec<CellGlyph>>) -> Result<Self, AsciiError> {
+        let width = rows.first().map_or(0, Vec::len);
+        if rows.iter().any(|row| row.len() != width) {
+            return Err(AsciiError::RaggedRows);
+        }
+
+        let (Ok(width), Ok(height)) = (u16::try_from(width), u16::try_from(rows.len())) else {
+            let clamp = |span: usize| span.min(u32::MAX as usize) as u32;
+            return Err(AsciiError::GridTooLarge(clamp(width), clamp(rows.len())));
+        };
+        Ok(Self { width, height, cells: rows.into_iter().flatten().collect() })
+    }
+
+    /// Borrow the cells as one slice per row.
+    pub fn to_2d(&self) -> Vec<&[CellGlyph]> {
+        self.cells.chunks(self.width.max(1) as usize).collect()
+    }
+
+    /// Every cell with its position, as `(row, column, cell)` in row-major order.
+    pub fn cells_with_points(&self) -> impl Iterator<Item = (u16, u16, &CellGlyph)> + '_ {
+        let width = self.width.max(1) as usize;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(index, cell)| ((index / width) as u16, (index % width) as u16, cell))
+    }
+
     pub fn rows(&self) -> impl Iterator<Item = String> + '_ {
         let width = self.width as usize;
-        self.cells.chunks(width).map(|row| row.iter().map(|cell| cell.ch).collect::<String>())
+        self.cells
+            .chunks(width.max(1))
+            .map(|row| row.iter().map(|cell| cell.ch).collect::<String>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_2d() {
+        let cells: Vec<CellGlyph> = "abcdef".chars().map(|ch| CellGlyph::new(ch, 1.)).collect();
+        let grid = GlyphGrid::new(3, 2, cells);
+
+        let rows = grid.to_2d();
+        assert_eq!(2, rows.len());
+        assert_eq!('d', rows[1][0].ch);
+
+        let rebuilt = GlyphGrid::from_2d(rows.iter().map(|row| row.to_vec()).collect()).unwrap();
+        assert_eq!(grid, rebuilt);
+    }
+
+    #[test]
+    fn alpha_blends_over_background() {
+        let mut cell = CellGlyph::new('#', 1.);
+        cell.alpha = 0.5;
+        assert_eq!(0.0, cell.background_alpha());
+
+        cell.bg = Some([255, 0, 0]);
+        assert_eq!(0.5, cell.background_alpha());
+        assert_eq!([128, 128, 128], cell.foreground_over([0; 3]));
+
+        cell.alpha = 1.0;
+        assert_eq!(1.0, cell.background_alpha());
+        assert_eq!([255; 3], cell.foreground_over([0; 3]));
+    }
+
+    #[test]
+    fn cells_with_points_are_row_major() {
+        let cells: Vec<CellGlyph> = "abcdef".chars().map(|ch| CellGlyph::new(ch, 1.)).collect();
+        let grid = GlyphGrid::new(3, 2, cells);
+
+        let points: Vec<_> =
+            grid.cells_with_points().map(|(row, column, cell)| (row, column, cell.ch)).collect();
+        assert_eq!(6, points.len());
+        assert_eq!((0, 0, 'a'), points[0]);
+        assert_eq!((0, 2, 'c'), points[2]);
+        assert_eq!((1, 0, 'd'), points[3]);
+        assert_eq!((1, 2, 'f'), points[5]);
+    }
+
+    #[test]
+    fn montage_places_tiles() {
+        let grids: Vec<GlyphGrid> = "abcd"
+            .chars()
+            .map(|ch| GlyphGrid::new(2, 1, vec![CellGlyph::new(ch, 1.); 2]))
+            .collect();
+
+        let montage = GlyphGrid::montage(&grids, 2, 1).unwrap();
+
+        assert_eq!((5, 3), (montage.width, montage.height));
+        assert_eq!(vec!["aa bb", "     ", "cc dd"], montage.rows().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn montage_rejects_oversized_sheets() {
+        let grids = vec![GlyphGrid::blank(300, 2); 300];
+
+        let err = GlyphGrid::montage(&grids, 300, 1).unwrap_err();
+
+        assert!(matches!(err, AsciiError::GridTooLarge(90299, 2)), "{err:?}");
+        assert!(GlyphGrid::montage(&grids, 1, 1).is_ok());
+    }
+
+    #[test]
+    fn pad_to_keeps_content() {
+        let grid = GlyphGrid::new(1, 1, vec![CellGlyph::new('x', 1.)]);
+
+        let padded = grid.pad_to(3, 2);
+
+        assert_eq!(vec!["x  ", "   "], padded.rows().collect::<Vec<_>>());
+        assert_eq!(0.0, padded.cells[1].alpha);
+    }
+
+    #[test]
+    fn tile_repeats_across_viewport() {
+        let glyphs = "abcdefghijklmnop";
+        let cells = glyphs.chars().map(|ch| CellGlyph::new(ch, 1.)).collect();
+        let grid = GlyphGrid::new(4, 4, cells);
+
+        let tiled = grid.tile(8, 8);
+
+        let rows: Vec<String> = tiled.rows().collect();
+        assert_eq!(8, rows.len());
+        assert_eq!("abcdabcd", rows[0]);
+        assert_eq!("mnopmnop", rows[3]);
+        assert_eq!(rows[..4], rows[4..]);
+        assert_eq!(vec!["ab"], grid.tile(2, 1).rows().collect::<Vec<_>>());
+
+        let shifted = grid.tile_offset(6, 2, 3, 5);
+        assert_eq!(vec!["hefghe", "lijkli"], shifted.rows().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn resize_nearest_samples_cells() {
+        let cells = "abcdefghijklmnop".chars().map(|ch| CellGlyph::new(ch, 1.)).collect();
+        let mut grid = GlyphGrid::new(4, 4, cells);
+        grid.cells[10].fg = [255, 0, 0];
+
+        let small = grid.resize_nearest(2, 2);
+        assert_eq!(vec!["fh", "np"], small.rows().collect::<Vec<_>>());
+        assert_eq!([255, 0, 0], grid.resize_nearest(1, 1).cells[0].fg);
+
+        let large = grid.resize_nearest(8, 2);
+        assert_eq!(vec!["eeffgghh", "mmnnoopp"], large.rows().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn ragged_rows_are_rejected() {
+        let rows = vec![vec![CellGlyph::new('a', 1.); 2], vec![CellGlyph::new('b', 1.)]];
+        assert!(matches!(GlyphGrid::from_2d(rows), Err(AsciiError::RaggedRows)));
+    }
+
+    #[test]
+    fn oversized_rows_are_rejected() {
+        let row = vec![CellGlyph::blank(); u16::MAX as usize + 1];
+        let err = GlyphGrid::from_2d(vec![row]).unwrap_err();
+        assert!(matches!(err, AsciiError::GridTooLarge(65536, 1)), "{err:?}");
+
+        let rows = vec![Vec::new(); u16::MAX as usize + 1];
+        let err = GlyphGrid::from_2d(rows).unwrap_err();
+        assert!(matches!(err, AsciiError::GridTooLarge(0, 65536)), "{err:?}");
     }
 }
@@ -0,0 +1,164 @@
+//! Export of glyph grids as HTML for embedding in web pages.
+
+use std::fmt::Write;
+
+use super::grid::{blend, CellGlyph, GlyphGrid};
+
+/// Page settings for [`to_html`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct HtmlOptions {
+    /// Color behind the glyphs.
+    pub background: [u8; 3],
+    /// CSS `font-family` of the glyphs, which should be monospace to keep columns aligned.
+    pub font_family: String,
+    /// Emit a complete document with `<html>` and `<body>` instead of just the art's `<div>`.
+    pub full_document: bool,
+}
+
+impl Default for HtmlOptions {
+    fn default() -> Self {
+        Self { background: [0, 0, 0], font_family: "monospace".into(), full_document: true }
+    }
+}
+
+/// Render `grid` as HTML, one `<div>` per row.
+///
+/// Runs of cells sharing the same colors are wrapped in a single `<span>`. Transparent cells are
+/// emitted without color so the background shows through, and partially transparent ones are
+/// blended over it.
+pub fn to_html(grid: &GlyphGrid, options: HtmlOptions) -> String {
+    let mut html = String::new();
+    if options.full_document {
+        html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n</head>\n");
+        let _ = writeln!(
+            html,
+            "<body style=\"margin:0;background-color:{}\">",
+            hex(options.background)
+        );
+    }
+
+    let _ = writeln!(
+        html,
+        "<div style=\"background-color:{};font-family:{};white-space:pre;line-height:1\">",
+        hex(options.background),
+        escape(&options.font_family),
+    );
+    for row in grid.cells.chunks(grid.width.max(1) as usize) {
+        html.push_str("<div>");
+        let mut style = None;
+        for cell in row {
+            let cell_style = span_style(cell, options.background);
+            if cell_style != style {
+                if style.is_some() {
+                    html.push_str("</span>");
+                }
+                if let Some(cell_style) = &cell_style {
+                    let _ = write!(html, "<span style=\"{}\">", cell_style);
+                }
+                style = cell_style;
+            }
+            push_escaped(&mut html, cell.ch);
+        }
+        if style.is_some() {
+            html.push_str("</span>");
+        }
+        html.push_str("</div>\n");
+    }
+    html.push_str("</div>\n");
+
+    if options.full_document {
+        html.push_str("</body>\n</html>\n");
+    }
+    html
+}
+
+/// Inline style of a cell's span on a page with the given `background`, or `None` for
+/// transparent cells.
+fn span_style(cell: &CellGlyph, background: [u8; 3]) -> Option<String> {
+    if cell.is_transparent() {
+        return None;
+    }
+
+    // The glyph is blended over the cell's own background, which is blended over the page.
+    let behind = cell.bg.map_or(background, |bg| blend(bg, background, cell.background_alpha()));
+    let fg = cell.foreground_over(behind);
+    Some(match cell.bg {
+        Some(_) => format!("color:{};background-color:{}", hex(fg), hex(behind)),
+        None => format!("color:{}", hex(fg)),
+    })
+}
+
+fn hex([r, g, b]: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        push_escaped(&mut escaped, ch);
+    }
+    escaped
+}
+
+/// Append `ch`, escaped if it is markup or would end an attribute value.
+pub(super) fn push_escaped(html: &mut String, ch: char) {
+    match ch {
+        '<' => html.push_str("&lt;"),
+        '>' => html.push_str("&gt;"),
+        '&' => html.push_str("&amp;"),
+        '"' => html.push_str("&quot;"),
+        _ => html.push(ch),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fragment_collapses_runs() {
+        let mut cells = vec![CellGlyph::new('<', 1.); 4];
+        cells[0].fg = [255, 0, 0];
+        cells[1].fg = [255, 0, 0];
+        cells[2].ch = '&';
+        cells[3] = CellGlyph::blank();
+        let grid = GlyphGrid::new(2, 2, cells);
+        let options = HtmlOptions {
+            background: [16, 32, 48],
+            font_family: "\"Fira Code\", monospace".into(),
+            full_document: false,
+        };
+
+        assert_eq!(
+            "<div style=\"background-color:#102030;font-family:&quot;Fira Code&quot;, \
+             monospace;white-space:pre;line-height:1\">\n<div><span \
+             style=\"color:#ff0000\">&lt;&lt;</span></div>\n<div><span \
+             style=\"color:#ffffff\">&amp;</span> </div>\n</div>\n",
+            to_html(&grid, options)
+        );
+    }
+
+    #[test]
+    fn translucent_cells_blend_over_page() {
+        let mut cell = CellGlyph::new('#', 1.);
+        cell.alpha = 0.5;
+        let page = [0, 0, 0];
+
+        assert_eq!(Some("color:#808080".into()), span_style(&cell, page));
+        cell.bg = Some([0, 0, 255]);
+        assert_eq!(Some("color:#8080c0;background-color:#000080".into()), span_style(&cell, page));
+        cell.alpha = 1.0;
+        assert_eq!(Some("color:#ffffff;background-color:#0000ff".into()), span_style(&cell, page));
+    }
+
+    #[test]
+    fn full_document() {
+        let grid = GlyphGrid::new(1, 1, vec![CellGlyph::new('#', 1.)]);
+
+        let html = to_html(&grid, HtmlOptions::default());
+
+        assert!(html.starts_with("<!DOCTYPE html>\n"), "{html}");
+        assert!(html.contains("<body style=\"margin:0;background-color:#000000\">"), "{html}");
+        assert!(html.ends_with("</div>\n</body>\n</html>\n"), "{html}");
+    }
+}
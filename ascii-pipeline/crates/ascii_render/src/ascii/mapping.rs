@@ -1,55 +1,296 @@
+use image::RgbaImage;
+
 use crate::image_pipeline::edges::EdgeSample;
+use crate::TRANSPARENT_ALPHA;
+
+use super::gradient::Gradient;
+use super::grid::{CellGlyph, GlyphGrid};
+
+/// How [`GlyphMapper::map_orientation`] draws edge cells and the cells between them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OrientationFill {
+    /// Glyph of cells without an edge.
+    pub background: char,
+    /// Draw edges dark over a light background instead of light over a dark one.
+    pub dark_edges: bool,
+}
 
-use super::{
-    gradient::Gradient,
-    grid::{CellGlyph, GlyphGrid},
-};
+impl Default for OrientationFill {
+    fn default() -> Self {
+        Self { background: ' ', dark_edges: false }
+    }
+}
+
+/// How [`GlyphMapper::map_intensity`] picks between the two glyphs closest to an intensity.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Dither {
+    /// Always pick the closest glyph.
+    #[default]
+    None,
+    /// Threshold each cell against a tiled blue-noise texture, so flat areas mix both glyphs in
+    /// proportion to the intensity without visible patterns, and unchanged areas of animation
+    /// frames keep their glyphs.
+    BlueNoise,
+    /// Offset each cell by its entry in a tiled 8x8 Bayer matrix, giving a regular crosshatch
+    /// that stays in place between animation frames.
+    Ordered,
+    /// Diffuse each cell's quantization error into its unvisited neighbors, see
+    /// [`GlyphMapper::map_intensity_dithered`].
+    FloydSteinberg,
+}
+
+/// Side length of [`BLUE_NOISE`].
+const BLUE_NOISE_SIZE: usize = 16;
+
+/// Void-and-cluster blue-noise ranks, each of `0..256` appearing once.
+#[rustfmt::skip]
+const BLUE_NOISE: [u8; BLUE_NOISE_SIZE * BLUE_NOISE_SIZE] = [
+    120, 61, 134, 223, 84, 33, 168, 12, 113, 225, 63, 246, 185, 233, 88, 169,
+    23, 206, 181, 17, 109, 214, 58, 140, 201, 24, 161, 93, 34, 133, 14, 221,
+    144, 73, 250, 49, 158, 187, 81, 251, 100, 51, 142, 210, 172, 57, 191, 106,
+    42, 167, 101, 126, 220, 3, 121, 40, 170, 231, 82, 8, 114, 255, 80, 232,
+    212, 11, 195, 31, 72, 239, 152, 196, 16, 127, 188, 222, 45, 157, 26, 128,
+    154, 87, 235, 143, 179, 94, 54, 108, 237, 65, 29, 105, 139, 207, 184, 66,
+    248, 47, 115, 62, 209, 20, 164, 217, 79, 146, 178, 243, 69, 90, 1, 118,
+    30, 190, 173, 6, 131, 254, 41, 136, 10, 204, 43, 159, 22, 229, 162, 218,
+    77, 148, 99, 226, 74, 182, 117, 192, 86, 247, 119, 97, 197, 130, 53, 103,
+    242, 19, 198, 44, 155, 96, 59, 230, 28, 165, 60, 5, 240, 39, 175, 202,
+    137, 64, 122, 238, 25, 211, 0, 149, 104, 224, 135, 183, 151, 71, 112, 9,
+    91, 213, 166, 85, 186, 111, 249, 174, 48, 75, 208, 32, 89, 205, 236, 160,
+    37, 252, 18, 55, 138, 38, 78, 123, 194, 13, 107, 253, 124, 15, 56, 189,
+    76, 145, 110, 228, 203, 163, 219, 21, 241, 141, 171, 50, 156, 227, 102, 129,
+    2, 199, 176, 68, 7, 98, 52, 150, 92, 36, 215, 83, 200, 27, 177, 216,
+    244, 95, 35, 153, 245, 125, 193, 234, 70, 180, 132, 4, 116, 67, 147, 46,
+];
+
+/// Blue-noise threshold in `(0, 1)` of the cell at `column` and `row`.
+fn blue_noise_threshold(column: usize, row: usize) -> f32 {
+    let rank = BLUE_NOISE[(row % BLUE_NOISE_SIZE) * BLUE_NOISE_SIZE + column % BLUE_NOISE_SIZE];
+    (rank as f32 + 0.5) / BLUE_NOISE.len() as f32
+}
+
+/// Side length of [`BAYER`].
+const BAYER_SIZE: usize = 8;
+
+/// Recursive Bayer matrix, each of `0..64` appearing once.
+#[rustfmt::skip]
+const BAYER: [u8; BAYER_SIZE * BAYER_SIZE] = [
+    0, 32, 8, 40, 2, 34, 10, 42,
+    48, 16, 56, 24, 50, 18, 58, 26,
+    12, 44, 4, 36, 14, 46, 6, 38,
+    60, 28, 52, 20, 62, 30, 54, 22,
+    3, 35, 11, 43, 1, 33, 9, 41,
+    51, 19, 59, 27, 49, 17, 57, 25,
+    15, 47, 7, 39, 13, 45, 5, 37,
+    63, 31, 55, 23, 61, 29, 53, 21,
+];
+
+/// Bayer threshold in `(0, 1)` of the cell at `column` and `row`.
+fn bayer_threshold(column: usize, row: usize) -> f32 {
+    let rank = BAYER[(row % BAYER_SIZE) * BAYER_SIZE + column % BAYER_SIZE];
+    (rank as f32 + 0.5) / BAYER.len() as f32
+}
 
 pub struct GlyphMapper {
     gradient: Gradient,
+    dither: Dither,
 }
 
 impl GlyphMapper {
     pub fn new(gradient: Gradient) -> Self {
-        Self { gradient }
+        Self { gradient, dither: Dither::None }
+    }
+
+    pub fn with_dither(mut self, dither: Dither) -> Self {
+        self.dither = dither;
+        self
     }
 
     pub fn map_intensity(&mut self, intensities: &[f32], width: u16, height: u16) -> GlyphGrid {
+        if self.dither == Dither::FloydSteinberg {
+            return self.map_intensity_dithered(intensities, width, height);
+        }
+
         let mut cells = Vec::with_capacity(intensities.len());
-        let gradient_chars = self.gradient.chars();
-        let max_index = gradient_chars.len() - 1;
+        let levels = self.gradient.len().saturating_sub(1) as f32;
 
-        for &value in intensities {
+        for (index, &value) in intensities.iter().enumerate() {
             let normalized = value.clamp(0.0, 1.0);
-            let index = (normalized * max_index as f32).round() as usize;
-            let ch = gradient_chars[index.min(max_index)];
+            let quantized = match self.dither {
+                Dither::BlueNoise if levels > 0.0 => {
+                    let width = usize::from(width.max(1));
+                    let scaled = normalized * levels;
+                    let threshold = blue_noise_threshold(index % width, index / width);
+                    let level =
+                        if scaled.fract() > threshold { scaled.ceil() } else { scaled.floor() };
+                    level / levels
+                },
+                // Shift by up to half a level either way, so rounding to the closest glyph picks
+                // the upper one in proportion to how far the intensity is past the lower one.
+                Dither::Ordered if levels > 0.0 => {
+                    let width = usize::from(width.max(1));
+                    let threshold = bayer_threshold(index % width, index / width);
+                    normalized + (0.5 - threshold) / levels
+                },
+                _ => normalized,
+            };
+            let ch = self.gradient.char_at(self.gradient.clamp_index(quantized));
             cells.push(CellGlyph::new(ch, normalized));
         }
 
         GlyphGrid::new(width, height, cells)
     }
 
+    /// Map intensities to glyphs with Floyd–Steinberg error diffusion.
+    ///
+    /// The difference between each cell's intensity and that of its glyph is passed on to the
+    /// following cell (7/16) and the three cells below (3/16, 5/16 and 1/16), so the average
+    /// intensity of an area is kept even with few glyphs. A glyph's intensity is its
+    /// [`Gradient::level`], the center of its bin for weighted gradients. Cells are shaded by their
+    /// undithered intensity.
+    pub fn map_intensity_dithered(
+        &mut self,
+        intensities: &[f32],
+        width: u16,
+        height: u16,
+    ) -> GlyphGrid {
+        let width = usize::from(width.max(1));
+        let mut cells = Vec::with_capacity(intensities.len());
+
+        // Errors carried into the current and the next row.
+        let mut current = vec![0.0; width];
+        let mut next = vec![0.0; width];
+        for row in intensities.chunks(width) {
+            for (x, &value) in row.iter().enumerate() {
+                let normalized = value.clamp(0.0, 1.0);
+                let target = (normalized + current[x]).clamp(0.0, 1.0);
+                let index = self.gradient.clamp_index(target);
+                cells.push(CellGlyph::new(self.gradient.char_at(index), normalized));
+
+                if self.gradient.len() < 2 {
+                    continue;
+                }
+                let error = target - self.gradient.level(index);
+                if x + 1 < width {
+                    current[x + 1] += error * 7.0 / 16.0;
+                    next[x + 1] += error / 16.0;
+                }
+                if x > 0 {
+                    next[x - 1] += error * 3.0 / 16.0;
+                }
+                next[x] += error * 5.0 / 16.0;
+            }
+
+            std::mem::swap(&mut current, &mut next);
+            next.fill(0.0);
+        }
+
+        GlyphGrid::new(width as u16, height, cells)
+    }
+
     pub fn map_orientation(
         &mut self,
         samples: &[EdgeSample],
         width: u16,
         height: u16,
+        fill: OrientationFill,
     ) -> GlyphGrid {
+        let shade = |intensity: f32| if fill.dark_edges { 1.0 - intensity } else { intensity };
+
         let mut cells = Vec::with_capacity(samples.len());
         for sample in samples {
             if !sample.active {
-                cells.push(CellGlyph::new(' ', 0.0));
+                cells.push(CellGlyph::new(fill.background, shade(0.0)));
                 continue;
             }
 
             let ch = orientation_glyph(sample.angle_degrees);
-            cells.push(CellGlyph::new(ch, sample.magnitude));
+            cells.push(CellGlyph::new(ch, shade(sample.magnitude)));
         }
 
         GlyphGrid::new(width, height, cells)
     }
 }
 
+/// Glyph whose upper half is drawn in the foreground color and lower half in the background.
+pub const UPPER_HALF_BLOCK: char = '▀';
+
+/// Map pairs of vertically stacked pixels to upper half blocks.
+///
+/// `pixels` must be twice as tall as the grid; the top pixel of each pair becomes the cell's
+/// foreground and the bottom pixel its background.
+pub fn map_half_blocks(pixels: &RgbaImage, width: u16, height: u16) -> GlyphGrid {
+    let mut cells = Vec::with_capacity(usize::from(width) * usize::from(height));
+    for row in 0..u32::from(height) {
+        for column in 0..u32::from(width) {
+            let [tr, tg, tb, ta] = pixels.get_pixel(column, row * 2).0;
+            let [br, bg, bb, ba] = pixels.get_pixel(column, row * 2 + 1).0;
+            cells.push(CellGlyph {
+                ch: UPPER_HALF_BLOCK,
+                fg: [tr, tg, tb],
+                bg: Some([br, bg, bb]),
+                alpha: ta.max(ba) as f32 / 255.0,
+            });
+        }
+    }
+
+    GlyphGrid::new(width, height, cells)
+}
+
+/// First glyph of the Unicode Braille block, with no dots raised.
+pub const BRAILLE_BLANK: char = '\u{2800}';
+
+/// Bit of each Braille dot, indexed by row and then column within the cell.
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// Map 2x4 blocks of pixels to Braille glyphs, raising the dots of pixels whose luminance
+/// reaches `threshold`.
+///
+/// `pixels` and `luminance` must be twice as wide and four times as tall as the grid.
+/// Transparent pixels never raise their dot. Each glyph is colored with the average of its
+/// raised dots, and takes the alpha of its most opaque pixel.
+pub fn map_braille(
+    pixels: &RgbaImage,
+    luminance: &[f32],
+    width: u16,
+    height: u16,
+    threshold: f32,
+) -> GlyphGrid {
+    let pixel_width = pixels.width() as usize;
+    let mut cells = Vec::with_capacity(usize::from(width) * usize::from(height));
+    for row in 0..u32::from(height) {
+        for column in 0..u32::from(width) {
+            let mut bits = 0;
+            let mut sum = [0u32; 3];
+            let mut raised = 0;
+            let mut alpha = 0;
+            for (dy, dots) in BRAILLE_DOTS.iter().enumerate() {
+                for (dx, &dot) in dots.iter().enumerate() {
+                    let (x, y) = (column * 2 + dx as u32, row * 4 + dy as u32);
+                    let [r, g, b, a] = pixels.get_pixel(x, y).0;
+                    alpha = alpha.max(a);
+                    let opaque = a as f32 / 255.0 >= TRANSPARENT_ALPHA;
+                    if !opaque || luminance[y as usize * pixel_width + x as usize] < threshold {
+                        continue;
+                    }
+
+                    bits |= dot;
+                    raised += 1;
+                    for (total, channel) in sum.iter_mut().zip([r, g, b]) {
+                        *total += u32::from(channel);
+                    }
+                }
+            }
+
+            let ch = char::from_u32(BRAILLE_BLANK as u32 + bits).unwrap_or(BRAILLE_BLANK);
+            let fg = if raised == 0 { [0; 3] } else { sum.map(|total| (total / raised) as u8) };
+            cells.push(CellGlyph { ch, fg, bg: None, alpha: alpha as f32 / 255.0 });
+        }
+    }
+
+    GlyphGrid::new(width, height, cells)
+}
+
 fn orientation_glyph(angle: f32) -> char {
     let angle = angle.rem_euclid(180.0);
     if (0.0..22.5).contains(&angle) || (157.5..180.0).contains(&angle) {
@@ -62,3 +303,133 @@ fn orientation_glyph(angle: f32) -> char {
         '\\'
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 3x3 cross of full-strength edges: horizontal in the middle row, vertical in the middle
+    /// column.
+    fn cross() -> Vec<EdgeSample> {
+        (0..9)
+            .map(|index| {
+                let (row, column) = (index / 3, index % 3);
+                let angle_degrees = if column == 1 { 90.0 } else { 0.0 };
+                EdgeSample { active: row == 1 || column == 1, magnitude: 1.0, angle_degrees }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn blue_noise_dither() {
+        let mut mapper = GlyphMapper::new(Gradient::binary()).with_dither(Dither::BlueNoise);
+        let midtones = vec![0.5; 32 * 32];
+
+        let grid = mapper.map_intensity(&midtones, 32, 32);
+        assert_eq!(grid, mapper.map_intensity(&midtones, 32, 32));
+
+        let ones = grid.cells.iter().filter(|cell| cell.ch == '1').count();
+        assert!((480..=544).contains(&ones), "{ones}");
+
+        // Exact levels stay on their glyph.
+        let flat = mapper.map_intensity(&[0.0, 1.0], 2, 1);
+        assert_eq!(vec!["01"], flat.rows().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn ordered_dither() {
+        let mut mapper = GlyphMapper::new(Gradient::binary()).with_dither(Dither::Ordered);
+        let midtones = vec![0.5; 24 * 16];
+
+        let grid = mapper.map_intensity(&midtones, 24, 16);
+        assert_eq!(grid, mapper.map_intensity(&midtones, 24, 16));
+
+        // Every 8x8 tile is the same, with exactly half of its cells raised.
+        let rows: Vec<String> = grid.rows().collect();
+        for (index, row) in rows.iter().enumerate() {
+            assert_eq!(row[..8].repeat(3), *row);
+            assert_eq!(rows[index % 8], *row);
+        }
+        let ones: usize = rows[..8].iter().map(|row| row[..8].matches('1').count()).sum();
+        assert_eq!(32, ones);
+
+        // A quarter gray raises a quarter of every tile.
+        let quarter = mapper.map_intensity(&[0.25; 8 * 8], 8, 8);
+        assert_eq!(16, quarter.cells.iter().filter(|cell| cell.ch == '1').count());
+
+        // Exact levels stay on their glyph.
+        let flat = mapper.map_intensity(&[0.0, 1.0, 0.0, 1.0], 4, 1);
+        assert_eq!(vec!["0101"], flat.rows().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn floyd_steinberg_dither() {
+        let mut mapper = GlyphMapper::new(Gradient::binary()).with_dither(Dither::FloydSteinberg);
+        let midtones = vec![0.5; 32 * 32];
+
+        let grid = mapper.map_intensity(&midtones, 32, 32);
+
+        let ones = grid.cells.iter().filter(|cell| cell.ch == '1').count();
+        assert!((500..=524).contains(&ones), "{ones}");
+        assert!(grid.cells.iter().all(|cell| cell.fg == [128; 3]));
+
+        // A quarter gray keeps its average, with errors reaching past the last column dropped.
+        let quarter = mapper.map_intensity(&[0.25; 9 * 8], 9, 8);
+        let ones = quarter.cells.iter().filter(|cell| cell.ch == '1').count();
+        assert!((15..=19).contains(&ones), "{ones}");
+
+        // Exact levels stay on their glyph, and single columns only diffuse downwards.
+        let flat = mapper.map_intensity(&[0.0, 1.0], 2, 1);
+        assert_eq!(vec!["01"], flat.rows().collect::<Vec<_>>());
+        let column = mapper.map_intensity(&[0.5; 4], 1, 4);
+        assert_eq!(vec!["1", "0", "1", "0"], column.rows().collect::<Vec<_>>());
+
+        // Weighted glyphs stand for the centers of their bins, 0.375 and 0.875 here.
+        let weighted = Gradient::binary().with_weights(vec![3., 1.]).unwrap();
+        let mut mapper = GlyphMapper::new(weighted.clone()).with_dither(Dither::FloydSteinberg);
+        let grid = mapper.map_intensity(&midtones, 32, 32);
+        let levels = grid.cells.iter().map(|cell| weighted.level((cell.ch == '1') as usize));
+        let mean = levels.sum::<f32>() / grid.cells.len() as f32;
+        assert!((mean - 0.5).abs() < 0.02, "{mean}");
+    }
+
+    #[test]
+    fn braille_dots() {
+        // Left column lit, except for a transparent pixel in the third row.
+        let pixels = RgbaImage::from_fn(2, 4, |x, y| match (x, y) {
+            (0, 2) => image::Rgba([255, 255, 255, 0]),
+            (0, _) => image::Rgba([200, 100, 0, 255]),
+            _ => image::Rgba([0, 0, 0, 255]),
+        });
+        let luminance: Vec<f32> =
+            (0..8).map(|index| if index % 2 == 0 { 1. } else { 0. }).collect();
+
+        let grid = map_braille(&pixels, &luminance, 1, 1, 0.5);
+
+        // Dots 1, 2 and 7.
+        assert_eq!('\u{2843}', grid.cells[0].ch);
+        assert_eq!([200, 100, 0], grid.cells[0].fg);
+        assert_eq!(1., grid.cells[0].alpha);
+
+        let dark = map_braille(&pixels, &[0.; 8], 1, 1, 0.5);
+        assert_eq!(BRAILLE_BLANK, dark.cells[0].ch);
+        let full = map_braille(&pixels, &[1.; 8], 1, 1, 0.);
+        assert_eq!('\u{28FB}', full.cells[0].ch);
+    }
+
+    #[test]
+    fn orientation_fills() {
+        let mut mapper = GlyphMapper::new(Gradient::default());
+
+        let light = mapper.map_orientation(&cross(), 3, 3, OrientationFill::default());
+        assert_eq!(vec![" | ", "-|-", " | "], light.rows().collect::<Vec<_>>());
+        assert_eq!([255; 3], light.cells[4].fg);
+        assert_eq!([0; 3], light.cells[0].fg);
+
+        let fill = OrientationFill { background: '█', dark_edges: true };
+        let dark = mapper.map_orientation(&cross(), 3, 3, fill);
+        assert_eq!(vec!["█|█", "-|-", "█|█"], dark.rows().collect::<Vec<_>>());
+        assert_eq!([0; 3], dark.cells[4].fg);
+        assert_eq!([255; 3], dark.cells[0].fg);
+    }
+}
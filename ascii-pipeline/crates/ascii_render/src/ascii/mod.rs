@@ -1,3 +1,7 @@
+pub mod ansi;
 pub mod gradient;
 pub mod grid;
+pub mod html;
 pub mod mapping;
+pub mod series;
+pub mod svg;
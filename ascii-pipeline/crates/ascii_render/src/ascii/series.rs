@@ -0,0 +1,764 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::io::{self, Write};
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+
+use crate::image_pipeline::loader::LoopCount;
+use crate::image_pipeline::resize::TargetGeometry;
+use crate::AsciiError;
+
+use super::grid::{CellGlyph, GlyphGrid};
+
+/// Display duration used for frames whose source carries no timing information.
+pub const DEFAULT_FRAME_DELAY: Duration = Duration::from_millis(100);
+
+/// Shortest display duration during live playback, capping redraws at about 60 FPS.
+pub const MIN_FRAME_INTERVAL: Duration = Duration::from_millis(16);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GlyphFrame {
+    pub grid: GlyphGrid,
+    /// How long the frame stays on screen.
+    pub duration: Duration,
+}
+
+/// Ordered sequence of rendered frames sharing a single layout.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GlyphGridSeries {
+    pub frames: Vec<GlyphFrame>,
+    /// Geometry every frame was rendered with, if known.
+    pub geometry: Option<TargetGeometry>,
+    /// How often the frames are played back.
+    pub loop_count: LoopCount,
+    /// Order the frames are played in.
+    pub play_mode: PlayMode,
+}
+
+/// What playback does after reaching the last frame.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PlayMode {
+    /// Start over from the first frame, as often as the loop count allows.
+    #[default]
+    Loop,
+    /// Play back to the first frame in reverse, then forwards again, as often as the loop count
+    /// allows.
+    PingPong,
+    /// Stop on the last frame.
+    Once,
+}
+
+/// Playback position within a [`GlyphGridSeries`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Playback {
+    /// Index of the frame currently shown.
+    pub index: usize,
+    /// Number of times playback has wrapped back to the first frame.
+    pub cycle: u16,
+    /// Whether ping-pong playback is heading back towards the first frame.
+    pub reversed: bool,
+}
+
+/// Frame a new player starts on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StartFrame {
+    #[default]
+    First,
+    /// A uniformly chosen frame, so several players of one series don't run in lockstep.
+    Random,
+    /// A fixed frame, wrapping around when past the end of the series.
+    Index(usize),
+}
+
+impl StartFrame {
+    /// Index of the starting frame in a series of `frames` frames.
+    pub fn resolve<R: Rng + ?Sized>(self, frames: usize, rng: &mut R) -> usize {
+        if frames == 0 {
+            return 0;
+        }
+
+        match self {
+            StartFrame::First => 0,
+            StartFrame::Random => rng.gen_range(0..frames),
+            StartFrame::Index(index) => index % frames,
+        }
+    }
+}
+
+/// Input to an interactive [`Player`], such as a key press in a terminal viewer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlayerControl {
+    TogglePause,
+    StepForward,
+    StepBackward,
+    Faster,
+    Slower,
+    Reverse,
+}
+
+/// Position, speed and direction of an interactive viewer playing a series of frames.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Player {
+    /// Position in the series; [`Playback::index`] is the frame currently shown.
+    pub playback: Playback,
+    pub paused: bool,
+    /// Multiplier applied to the frame rate.
+    pub speed: f32,
+    /// Play the frames from last to first.
+    pub reversed: bool,
+}
+
+impl Default for Player {
+    fn default() -> Self {
+        Self { playback: Playback::default(), paused: false, speed: 1.0, reversed: false }
+    }
+}
+
+impl Player {
+    /// Slowest and fastest playback speed reachable with [`PlayerControl::Slower`] and
+    /// [`PlayerControl::Faster`].
+    pub const SPEED_RANGE: (f32, f32) = (0.125, 8.0);
+
+    /// Apply `control` to playback of a series of `frames` frames.
+    ///
+    /// Steps move one frame forwards or backwards in frame order, wrapping around at either end,
+    /// whether or not playback is paused.
+    pub fn apply(&mut self, control: PlayerControl, frames: usize) {
+        let (min_speed, max_speed) = Self::SPEED_RANGE;
+        match control {
+            PlayerControl::TogglePause => self.paused = !self.paused,
+            PlayerControl::StepForward => self.step(frames, true),
+            PlayerControl::StepBackward => self.step(frames, false),
+            PlayerControl::Faster => self.speed = (self.speed * 2.0).min(max_speed),
+            PlayerControl::Slower => self.speed = (self.speed / 2.0).max(min_speed),
+            PlayerControl::Reverse => self.reversed = !self.reversed,
+        }
+    }
+
+    /// Move to the next frame of `series` in the playing direction, unless paused.
+    ///
+    /// Playback follows the play mode and loop count of `series`, mirrored while reversed. Once
+    /// it's over, the player pauses on the frame it ended on.
+    pub fn tick(&mut self, series: &GlyphGridSeries) {
+        if self.paused {
+            return;
+        }
+
+        let advanced = if self.reversed {
+            let last = series.len().saturating_sub(1);
+            let mirror = |playback: Playback| Playback {
+                index: last.saturating_sub(playback.index),
+                ..playback
+            };
+            let mut mirrored = mirror(self.playback);
+            let advanced = series.advance(&mut mirrored);
+            self.playback = mirror(mirrored);
+            advanced
+        } else {
+            series.advance(&mut self.playback)
+        };
+        self.paused = !advanced;
+    }
+
+    /// How long a frame with the source `delay` stays on screen at the current speed.
+    ///
+    /// Never shorter than [`MIN_FRAME_INTERVAL`], so animations with very short frame delays don't
+    /// redraw the terminal more often than it can show.
+    pub fn delay(&self, delay: Duration) -> Duration {
+        delay.div_f64(self.speed as f64).max(MIN_FRAME_INTERVAL)
+    }
+
+    /// One-line summary of the playback state for a status bar.
+    pub fn status_line(&self, frames: usize) -> String {
+        let mut status = format!("frame {}/{}  {}x", self.playback.index + 1, frames, self.speed);
+        if self.reversed {
+            status.push_str("  reversed");
+        }
+        if self.paused {
+            status.push_str("  paused");
+        }
+        status
+    }
+
+    fn step(&mut self, frames: usize, forward: bool) {
+        let index = &mut self.playback.index;
+        if frames == 0 {
+            *index = 0;
+        } else if forward {
+            *index = (*index + 1) % frames;
+        } else {
+            *index = (*index % frames + frames - 1) % frames;
+        }
+    }
+}
+
+/// Opacity ramp from 0 to 1 over a fixed duration, used to fade playback in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FadeIn {
+    pub start: Instant,
+    pub duration: Duration,
+}
+
+impl FadeIn {
+    pub fn new(start: Instant, duration: Duration) -> Self {
+        Self { start, duration }
+    }
+
+    /// Fraction of the fade completed at `now`, from `0.0` to `1.0`.
+    ///
+    /// Multiply cell alpha by this value; it stays at `1.0` once the fade is over.
+    pub fn progress(&self, now: Instant) -> f32 {
+        let elapsed = now.saturating_duration_since(self.start);
+        if elapsed >= self.duration {
+            return 1.;
+        }
+
+        elapsed.as_secs_f32() / self.duration.as_secs_f32()
+    }
+
+    pub fn is_complete(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.start) >= self.duration
+    }
+}
+
+/// Constant pan of a tiled grid, in cells per second.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Drift {
+    pub columns_per_second: f32,
+    pub lines_per_second: f32,
+}
+
+impl Drift {
+    pub fn new(columns_per_second: f32, lines_per_second: f32) -> Self {
+        Self { columns_per_second, lines_per_second }
+    }
+
+    /// Tile offset reached after `elapsed`, wrapped to a `width`x`height` grid.
+    ///
+    /// Pass the result to [`GlyphGrid::tile_offset`]. Positive velocities move the sampled
+    /// position right and down.
+    pub fn offset(&self, elapsed: Duration, width: u16, height: u16) -> (u16, u16) {
+        let wrap = |velocity: f32, size: u16| {
+            let cells = (velocity * elapsed.as_secs_f32()).floor();
+            cells.rem_euclid(size.max(1) as f32) as u16
+        };
+        (wrap(self.columns_per_second, width), wrap(self.lines_per_second, height))
+    }
+}
+
+impl GlyphGridSeries {
+    pub fn new(geometry: Option<TargetGeometry>) -> Self {
+        Self {
+            frames: Vec::new(),
+            geometry,
+            loop_count: LoopCount::Infinite,
+            play_mode: PlayMode::Loop,
+        }
+    }
+
+    pub fn push(&mut self, grid: GlyphGrid, duration: Duration) {
+        self.frames.push(GlyphFrame { grid, duration });
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn frame(&self, index: usize) -> Option<&GlyphGrid> {
+        self.frames.get(index).map(|frame| &frame.grid)
+    }
+
+    /// How long the frame at `index` is shown.
+    pub fn delay(&self, index: usize) -> Option<Duration> {
+        self.frames.get(index).map(|frame| frame.duration)
+    }
+
+    /// Length of one pass through every frame.
+    pub fn total_duration(&self) -> Duration {
+        self.frames.iter().map(|frame| frame.duration).sum()
+    }
+
+    /// Playback position for a player beginning at `start`.
+    pub fn start_playback<R: Rng + ?Sized>(&self, start: StartFrame, rng: &mut R) -> Playback {
+        Playback { index: start.resolve(self.frames.len(), rng), cycle: 0, reversed: false }
+    }
+
+    pub fn grids(&self) -> impl Iterator<Item = &GlyphGrid> + '_ {
+        self.frames.iter().map(|frame| &frame.grid)
+    }
+
+    /// New series with `transform` applied to every frame, keeping durations and geometry.
+    ///
+    /// Fails with [`AsciiError::GeometryMismatch`] if the series has a geometry and a transformed
+    /// frame differs in size from its source frame.
+    pub fn map<F>(&self, mut transform: F) -> Result<GlyphGridSeries, AsciiError>
+    where
+        F: FnMut(&GlyphGrid) -> GlyphGrid,
+    {
+        let mut series = GlyphGridSeries {
+            frames: Vec::with_capacity(self.len()),
+            geometry: self.geometry,
+            loop_count: self.loop_count,
+            play_mode: self.play_mode,
+        };
+        for frame in &self.frames {
+            let grid = transform(&frame.grid);
+            let resized = grid.width != frame.grid.width || grid.height != frame.grid.height;
+            if self.geometry.is_some() && resized {
+                return Err(AsciiError::GeometryMismatch);
+            }
+            series.push(grid, frame.duration);
+        }
+        Ok(series)
+    }
+
+    /// Write every frame to `dir` as `frame_0000.txt`, `frame_0001.txt` and so on.
+    ///
+    /// `formatter` turns each grid into the file contents, such as plain rows or ANSI text. Frame
+    /// numbers are padded to four digits, or more when the series needs them.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn write_frames<F>(&self, dir: &Path, formatter: F) -> io::Result<()>
+    where
+        F: Fn(&GlyphGrid) -> String,
+    {
+        for (index, grid) in self.grids().enumerate() {
+            Self::write_frame(dir, index, self.len(), &formatter(grid))?;
+        }
+        Ok(())
+    }
+
+    /// Write `contents` as frame `index` of `count` to `dir`, the way
+    /// [`GlyphGridSeries::write_frames`] does.
+    ///
+    /// This lets frames be written one by one as they are produced, without a series holding all
+    /// of them.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn write_frame(dir: &Path, index: usize, count: usize, contents: &str) -> io::Result<()> {
+        let mut file = std::fs::File::create(dir.join(Self::frame_file_name(index, count)))?;
+        writeln!(file, "{}", contents)
+    }
+
+    /// Name [`GlyphGridSeries::write_frames`] gives frame `index` of a series of `count` frames.
+    pub fn frame_file_name(index: usize, count: usize) -> String {
+        let digits = count.saturating_sub(1).to_string().len().max(4);
+        format!("frame_{:0digits$}.txt", index)
+    }
+
+    /// Move `playback` to the next frame, following the play mode and loop count.
+    ///
+    /// Returns `false` and leaves `playback` where it is once playback is over: on the last frame
+    /// for [`PlayMode::Once`] or an exhausted loop, and on the first frame for an exhausted
+    /// ping-pong.
+    pub fn advance(&self, playback: &mut Playback) -> bool {
+        if self.frames.is_empty() {
+            return false;
+        }
+
+        if playback.reversed {
+            if playback.index > 0 {
+                playback.index -= 1;
+                return true;
+            }
+            playback.reversed = false;
+        } else if playback.index + 1 < self.frames.len() {
+            playback.index += 1;
+            return true;
+        }
+
+        let exhausted = match self.loop_count {
+            LoopCount::Finite(count) => playback.cycle.saturating_add(1) >= count,
+            LoopCount::Infinite => false,
+        };
+        match self.play_mode {
+            PlayMode::Once => false,
+            PlayMode::PingPong if playback.index > 0 => {
+                playback.reversed = true;
+                playback.index -= 1;
+                true
+            },
+            _ if exhausted => false,
+            PlayMode::PingPong => {
+                playback.index = (self.frames.len() > 1) as usize;
+                playback.cycle = playback.cycle.saturating_add(1);
+                true
+            },
+            PlayMode::Loop => {
+                playback.index = 0;
+                playback.cycle = playback.cycle.saturating_add(1);
+                true
+            },
+        }
+    }
+
+    /// Copy of the series with its frames laid out in playback order for a single pass of the
+    /// play mode, for exports that can only loop.
+    ///
+    /// For [`PlayMode::PingPong`], the frames between the last and the first are appended in
+    /// reverse; other modes keep the frames as they are.
+    pub fn bake_play_mode(&self) -> GlyphGridSeries {
+        let mut series = self.clone();
+        if self.play_mode == PlayMode::PingPong && self.frames.len() > 2 {
+            let echo = self.frames[1..self.frames.len() - 1].iter().rev().cloned();
+            series.frames.extend(echo);
+        }
+        series.play_mode = PlayMode::Loop;
+        series
+    }
+
+    /// Merge runs of identical consecutive frames into one frame spanning their total duration.
+    pub fn coalesce_duplicates(&mut self) {
+        self.frames.dedup_by(|next, kept| {
+            let duplicate = next.grid == kept.grid;
+            if duplicate {
+                kept.duration += next.duration;
+            }
+            duplicate
+        });
+    }
+
+    /// Transition frames blending the last frame of `a` into the first frame of `b`.
+    ///
+    /// The returned series holds `frames` intermediate grids, excluding both endpoints. Colors are
+    /// interpolated linearly while each glyph is taken from whichever side dominates the blend.
+    pub fn crossfade(
+        a: &GlyphGridSeries,
+        b: &GlyphGridSeries,
+        frames: usize,
+    ) -> Result<GlyphGridSeries, AsciiError> {
+        let mut series = GlyphGridSeries::new(a.geometry.or(b.geometry));
+        let (Some(from), Some(to)) = (a.frames.last(), b.frames.first()) else {
+            return Ok(series);
+        };
+
+        if from.grid.width != to.grid.width || from.grid.height != to.grid.height {
+            return Err(AsciiError::GeometryMismatch);
+        }
+
+        for step in 1..=frames {
+            let t = step as f32 / (frames + 1) as f32;
+            let cells = from
+                .grid
+                .cells
+                .iter()
+                .zip(&to.grid.cells)
+                .map(|(from, to)| blend_cell(from, to, t))
+                .collect();
+            let duration = if t < 0.5 { from.duration } else { to.duration };
+            series.push(GlyphGrid::new(from.grid.width, from.grid.height, cells), duration);
+        }
+
+        Ok(series)
+    }
+}
+
+fn blend_cell(from: &CellGlyph, to: &CellGlyph, t: f32) -> CellGlyph {
+    let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    let lerp_rgb = |a: [u8; 3], b: [u8; 3]| [lerp(a[0], b[0]), lerp(a[1], b[1]), lerp(a[2], b[2])];
+    let dominant = if t < 0.5 { from } else { to };
+
+    let bg = match (from.bg, to.bg) {
+        (Some(from), Some(to)) => Some(lerp_rgb(from, to)),
+        _ => dominant.bg,
+    };
+
+    CellGlyph {
+        ch: dominant.ch,
+        fg: lerp_rgb(from.fg, to.fg),
+        bg,
+        alpha: from.alpha + (to.alpha - from.alpha) * t,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use super::*;
+
+    fn letters(letters: &str) -> GlyphGridSeries {
+        let mut series = GlyphGridSeries::default();
+        for ch in letters.chars() {
+            series.push(GlyphGrid::new(1, 1, vec![CellGlyph::new(ch, 1.)]), DEFAULT_FRAME_DELAY);
+        }
+        series
+    }
+
+    #[test]
+    fn player_controls() {
+        let series = letters("abcd");
+        let mut player = Player::default();
+
+        player.apply(PlayerControl::StepBackward, 4);
+        assert_eq!(3, player.playback.index);
+        player.apply(PlayerControl::StepForward, 4);
+        assert_eq!(0, player.playback.index);
+
+        player.tick(&series);
+        assert_eq!(1, player.playback.index);
+        player.apply(PlayerControl::Reverse, 4);
+        player.tick(&series);
+        player.tick(&series);
+        assert_eq!(3, player.playback.index);
+
+        player.apply(PlayerControl::TogglePause, 4);
+        player.tick(&series);
+        assert_eq!(3, player.playback.index);
+        assert_eq!("frame 4/4  1x  reversed  paused", player.status_line(4));
+
+        for _ in 0..10 {
+            player.apply(PlayerControl::Faster, 4);
+        }
+        assert_eq!(8.0, player.speed);
+        assert_eq!(Duration::from_millis(20), player.delay(Duration::from_millis(160)));
+        assert_eq!(MIN_FRAME_INTERVAL, player.delay(Duration::from_millis(80)));
+        for _ in 0..10 {
+            player.apply(PlayerControl::Slower, 4);
+        }
+        assert_eq!(0.125, player.speed);
+        player.speed = 1.0;
+        assert_eq!(MIN_FRAME_INTERVAL, player.delay(Duration::from_millis(5)));
+        assert_eq!(Duration::from_millis(40), player.delay(Duration::from_millis(40)));
+
+        player.apply(PlayerControl::StepForward, 0);
+        assert_eq!(0, player.playback.index);
+    }
+
+    #[test]
+    fn player_follows_play_mode() {
+        let mut series = letters("abc");
+        let shown = |series: &GlyphGridSeries, mut player: Player| {
+            let mut shown = vec![player.playback.index];
+            while !player.paused && shown.len() < 9 {
+                player.tick(series);
+                shown.push(player.playback.index);
+            }
+            (shown, player.paused)
+        };
+
+        series.play_mode = PlayMode::Once;
+        assert_eq!((vec![0, 1, 2, 2], true), shown(&series, Player::default()));
+        let reversed = Player {
+            playback: Playback { index: 2, ..Playback::default() },
+            reversed: true,
+            ..Player::default()
+        };
+        assert_eq!((vec![2, 1, 0, 0], true), shown(&series, reversed));
+
+        series.play_mode = PlayMode::PingPong;
+        assert_eq!((vec![0, 1, 2, 1, 0, 1, 2, 1, 0], false), shown(&series, Player::default()));
+    }
+
+    fn solid(ch: char, intensity: f32) -> GlyphGridSeries {
+        let mut series = GlyphGridSeries::default();
+        series.push(
+            GlyphGrid::new(2, 2, vec![CellGlyph::new(ch, intensity); 4]),
+            DEFAULT_FRAME_DELAY,
+        );
+        series
+    }
+
+    #[test]
+    fn crossfade_black_to_white() {
+        let black = solid('@', 0.);
+        let white = solid(' ', 1.);
+
+        let fade = GlyphGridSeries::crossfade(&black, &white, 5).unwrap();
+
+        assert_eq!(5, fade.len());
+        let grays: Vec<u8> = fade.grids().map(|grid| grid.cells[0].fg[0]).collect();
+        assert!(grays.windows(2).all(|pair| pair[0] < pair[1]), "not monotonic: {grays:?}");
+        assert!(grays[0] > 0 && grays[4] < 255);
+        for grid in fade.grids() {
+            assert!(grid.cells.iter().all(|cell| cell.fg == grid.cells[0].fg));
+        }
+
+        let glyphs: String = fade.grids().map(|grid| grid.cells[0].ch).collect();
+        assert_eq!("@@   ", glyphs);
+    }
+
+    #[test]
+    fn finite_loop_halts() {
+        let mut series = solid(' ', 0.);
+        series.push(GlyphGrid::new(2, 2, vec![CellGlyph::new('#', 1.); 4]), DEFAULT_FRAME_DELAY);
+        series.loop_count = LoopCount::Finite(2);
+
+        let mut playback = Playback::default();
+        let mut shown = vec![playback.index];
+        while series.advance(&mut playback) {
+            shown.push(playback.index);
+        }
+
+        assert_eq!(vec![0, 1, 0, 1], shown);
+        assert_eq!(Playback { index: 1, cycle: 1, reversed: false }, playback);
+        assert!(!series.advance(&mut playback));
+        assert_eq!(1, playback.index);
+    }
+
+    #[test]
+    fn play_modes() {
+        let mut series = letters("abc");
+        let shown = |series: &GlyphGridSeries, steps: usize| {
+            let mut playback = Playback::default();
+            let mut shown = vec![playback.index];
+            while shown.len() <= steps && series.advance(&mut playback) {
+                shown.push(playback.index);
+            }
+            shown
+        };
+
+        series.play_mode = PlayMode::Once;
+        assert_eq!(vec![0, 1, 2], shown(&series, 10));
+
+        series.play_mode = PlayMode::PingPong;
+        assert_eq!(vec![0, 1, 2, 1, 0, 1, 2, 1, 0], shown(&series, 8));
+        series.loop_count = LoopCount::Finite(1);
+        assert_eq!(vec![0, 1, 2, 1, 0], shown(&series, 10));
+
+        let baked: String = series.bake_play_mode().grids().map(|grid| grid.cells[0].ch).collect();
+        assert_eq!("abcb", baked);
+    }
+
+    #[test]
+    fn infinite_loop_wraps() {
+        let series = solid(' ', 0.);
+        let mut playback = Playback::default();
+        for _ in 0..10 {
+            assert!(series.advance(&mut playback));
+        }
+        assert_eq!(Playback { index: 0, cycle: 10, reversed: false }, playback);
+    }
+
+    #[test]
+    fn total_duration_sums_delays() {
+        let mut series = GlyphGridSeries::default();
+        assert_eq!(Duration::ZERO, series.total_duration());
+
+        let grid = GlyphGrid::new(1, 1, vec![CellGlyph::new('#', 1.)]);
+        for millis in [30, 70, 120] {
+            series.push(grid.clone(), Duration::from_millis(millis));
+        }
+
+        assert_eq!(Some(Duration::from_millis(70)), series.delay(1));
+        assert_eq!(None, series.delay(3));
+        let sum: Duration = (0..series.len()).filter_map(|index| series.delay(index)).sum();
+        assert_eq!(sum, series.total_duration());
+        assert_eq!(Duration::from_millis(220), series.total_duration());
+    }
+
+    #[test]
+    fn start_frame_selection() {
+        let mut series = GlyphGridSeries::default();
+        for _ in 0..5 {
+            series.push(GlyphGrid::new(1, 1, vec![CellGlyph::new('#', 1.)]), DEFAULT_FRAME_DELAY);
+        }
+        let mut rng = StdRng::seed_from_u64(7);
+
+        assert_eq!(3, series.start_playback(StartFrame::Index(3), &mut rng).index);
+        assert_eq!(1, series.start_playback(StartFrame::Index(6), &mut rng).index);
+        assert_eq!(0, series.start_playback(StartFrame::First, &mut rng).index);
+        for _ in 0..20 {
+            assert!(series.start_playback(StartFrame::Random, &mut rng).index < 5);
+        }
+        assert_eq!(0, StartFrame::Index(3).resolve(0, &mut rng));
+    }
+
+    #[test]
+    fn fade_in_ramps_opacity() {
+        let start = Instant::now();
+        let fade = FadeIn::new(start, Duration::from_millis(400));
+
+        assert!(fade.progress(start) < 0.01);
+        assert!((fade.progress(start + Duration::from_millis(100)) - 0.25).abs() < 0.01);
+        assert!(!fade.is_complete(start + Duration::from_millis(399)));
+        assert_eq!(1., fade.progress(start + Duration::from_millis(400)));
+        assert_eq!(1., fade.progress(start + Duration::from_secs(5)));
+        assert_eq!(1., FadeIn::new(start, Duration::ZERO).progress(start));
+    }
+
+    #[test]
+    fn map_preserves_series() {
+        let mut series = solid('#', 1.);
+        series.push(GlyphGrid::new(2, 2, vec![CellGlyph::new('.', 0.2); 4]), MIN_FRAME_INTERVAL);
+        series.geometry = Some(TargetGeometry { columns: 2, rows: 2, cell_aspect: 0.5 });
+        series.loop_count = LoopCount::Finite(3);
+
+        assert_eq!(series, series.map(GlyphGrid::clone).unwrap());
+
+        let result = series.map(|grid| grid.pad_to(3, 2));
+        assert!(matches!(result, Err(AsciiError::GeometryMismatch)));
+
+        series.geometry = None;
+        assert_eq!(3, series.map(|grid| grid.pad_to(3, 2)).unwrap().frames[1].grid.width);
+    }
+
+    #[test]
+    fn write_frames_names_files() {
+        let mut series = solid('#', 1.);
+        series.push(GlyphGrid::new(2, 2, vec![CellGlyph::new('.', 0.2); 4]), DEFAULT_FRAME_DELAY);
+        series.push(GlyphGrid::new(2, 2, vec![CellGlyph::new('o', 0.6); 4]), DEFAULT_FRAME_DELAY);
+        let dir = std::env::temp_dir().join(format!("ascii_render_frames_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        series.write_frames(&dir, |grid| grid.rows().collect::<Vec<_>>().join("\n")).unwrap();
+
+        let mut names: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        let last = std::fs::read_to_string(dir.join("frame_0002.txt")).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(vec!["frame_0000.txt", "frame_0001.txt", "frame_0002.txt"], names);
+        assert_eq!("oo\noo\n", last);
+    }
+
+    #[test]
+    fn drift_offset_wraps() {
+        let drift = Drift::new(2., -1.);
+
+        assert_eq!((0, 0), drift.offset(Duration::ZERO, 4, 4));
+        assert_eq!((1, 1), drift.offset(Duration::from_millis(2500), 4, 4));
+        assert_eq!((2, 3), drift.offset(Duration::from_secs(1), 8, 4));
+        assert_eq!((0, 0), Drift::default().offset(Duration::from_secs(60), 4, 4));
+    }
+
+    #[test]
+    fn coalesce_identical_frames() {
+        let grid = GlyphGrid::new(2, 1, vec![CellGlyph::new('#', 0.5); 2]);
+        let mut other = grid.clone();
+        other.cells[1].fg = [0, 0, 255];
+
+        let mut series = GlyphGridSeries::default();
+        for _ in 0..3 {
+            series.push(grid.clone(), DEFAULT_FRAME_DELAY);
+        }
+        series.push(other.clone(), DEFAULT_FRAME_DELAY);
+        series.push(grid.clone(), DEFAULT_FRAME_DELAY);
+
+        series.coalesce_duplicates();
+
+        assert_eq!(3, series.len());
+        assert_eq!(
+            GlyphFrame { grid: grid.clone(), duration: DEFAULT_FRAME_DELAY * 3 },
+            series.frames[0]
+        );
+        assert_eq!(GlyphFrame { grid: other, duration: DEFAULT_FRAME_DELAY }, series.frames[1]);
+        assert_eq!(GlyphFrame { grid, duration: DEFAULT_FRAME_DELAY }, series.frames[2]);
+    }
+
+    #[test]
+    fn crossfade_geometry_mismatch() {
+        let mut wide = GlyphGridSeries::default();
+        wide.push(GlyphGrid::new(4, 1, vec![CellGlyph::new(' ', 0.); 4]), DEFAULT_FRAME_DELAY);
+
+        let result = GlyphGridSeries::crossfade(&solid(' ', 0.), &wide, 3);
+        assert!(matches!(result, Err(AsciiError::GeometryMismatch)));
+    }
+}
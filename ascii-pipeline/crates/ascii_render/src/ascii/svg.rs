@@ -0,0 +1,100 @@
+//! Export of glyph grids as SVG images that keep each cell's color and position.
+
+use std::fmt::Write;
+
+use super::grid::GlyphGrid;
+use super::html::push_escaped;
+
+/// Render `grid` as an SVG image with `cell_width`x`cell_height` cells.
+///
+/// Every visible glyph becomes a `<text>` element at the top left corner of its cell, filled with
+/// the cell's foreground color, and cells with a background color get a `<rect>` behind it.
+/// Partially transparent cells keep their alpha as the opacity of both. Transparent cells are
+/// skipped entirely, as are blank glyphs without a background.
+pub fn to_svg(grid: &GlyphGrid, cell_width: f32, cell_height: f32) -> String {
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" \
+         font-family=\"monospace\" font-size=\"{}\" dominant-baseline=\"text-before-edge\">",
+        grid.width as f32 * cell_width,
+        grid.height as f32 * cell_height,
+        cell_height,
+    );
+
+    for (row, column, cell) in grid.cells_with_points() {
+        if cell.is_transparent() {
+            continue;
+        }
+
+        let (x, y) = (column as f32 * cell_width, row as f32 * cell_height);
+        if let Some([r, g, b]) = cell.bg {
+            let _ = writeln!(
+                svg,
+                "<rect x=\"{x}\" y=\"{y}\" width=\"{cell_width}\" height=\"{cell_height}\" \
+                 fill=\"#{r:02x}{g:02x}{b:02x}\"{}/>",
+                opacity(cell.background_alpha()),
+            );
+        }
+        if cell.ch.is_whitespace() {
+            continue;
+        }
+
+        let [r, g, b] = cell.fg;
+        let _ = write!(
+            svg,
+            "<text x=\"{x}\" y=\"{y}\" fill=\"#{r:02x}{g:02x}{b:02x}\"{}>",
+            opacity(cell.alpha)
+        );
+        push_escaped(&mut svg, cell.ch);
+        svg.push_str("</text>\n");
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// `fill-opacity` attribute for a fill with opacity `alpha`, empty when fully opaque.
+fn opacity(alpha: f32) -> String {
+    if alpha >= 1.0 {
+        String::new()
+    } else {
+        format!(" fill-opacity=\"{}\"", alpha.max(0.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::grid::CellGlyph;
+    use super::*;
+
+    #[test]
+    fn cells_become_positioned_text() {
+        let mut cells = vec![CellGlyph::new('<', 1.); 4];
+        cells[0].fg = [255, 0, 0];
+        cells[0].alpha = 0.75;
+        cells[1].bg = Some([0, 0, 255]);
+        cells[2] = CellGlyph::blank();
+        cells[3].ch = ' ';
+        cells[3].bg = Some([0, 255, 0]);
+        cells[3].alpha = 0.5;
+        let grid = GlyphGrid::new(2, 2, cells);
+
+        let svg = to_svg(&grid, 8., 16.);
+
+        assert!(
+            svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"16\" height=\"32\"")
+        );
+        assert_eq!(
+            vec![
+                "<text x=\"0\" y=\"0\" fill=\"#ff0000\" fill-opacity=\"0.75\">&lt;</text>",
+                "<rect x=\"8\" y=\"0\" width=\"8\" height=\"16\" fill=\"#0000ff\"/>",
+                "<text x=\"8\" y=\"0\" fill=\"#ffffff\">&lt;</text>",
+                "<rect x=\"8\" y=\"16\" width=\"8\" height=\"16\" fill=\"#00ff00\" \
+                 fill-opacity=\"0.5\"/>",
+                "</svg>",
+            ],
+            svg.lines().skip(1).collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,20 @@
+//! Hooks for the benchmark harness in `benches/`.
+//!
+//! Exposes the internal pipeline stages so they can be timed in isolation. Not a stable API.
+
+use image::{DynamicImage, Rgb, RgbImage};
+
+pub use crate::image_pipeline::adjust::extract_luma;
+pub use crate::image_pipeline::edges::sobel_map;
+
+/// Deterministic `width`x`height` test image with smooth gradients and sharp ring edges.
+pub fn synthetic_image(width: u32, height: u32) -> DynamicImage {
+    let (center_x, center_y) = (width as f32 / 2., height as f32 / 2.);
+    DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |x, y| {
+        let distance = (x as f32 - center_x).hypot(y as f32 - center_y);
+        let ring = if (distance as u32 / 8).is_multiple_of(2) { 255 } else { 0 };
+        let red = (x * 255 / width.max(1)) as u8;
+        let green = (y * 255 / height.max(1)) as u8;
+        Rgb([red, green, ring])
+    }))
+}
@@ -1,14 +1,77 @@
 use image::DynamicImage;
 
-pub fn extract_luma(image: &DynamicImage, invert: bool) -> Vec<f32> {
+/// Curve compressing high dynamic range luminance into `[0, 1]`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ToneMap {
+    /// Clamp values above 1.
+    #[default]
+    None,
+    /// Reinhard's `x / (1 + x)` operator.
+    Reinhard,
+    /// Narkowicz's fit of the ACES filmic curve.
+    Aces,
+}
+
+impl ToneMap {
+    pub fn apply(self, value: f32) -> f32 {
+        let value = value.max(0.0);
+        let mapped = match self {
+            ToneMap::None => value,
+            ToneMap::Reinhard => value / (1.0 + value),
+            ToneMap::Aces => {
+                (value * (2.51 * value + 0.03)) / (value * (2.43 * value + 0.59) + 0.14)
+            },
+        };
+        mapped.clamp(0.0, 1.0)
+    }
+}
+
+/// Strength of the [`Companding`] curves.
+const COMPANDING_STRENGTH: f32 = 9.0;
+
+/// Logarithmic or exponential reshaping of luminance in `[0, 1]`, keeping both endpoints fixed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Companding {
+    #[default]
+    None,
+    /// `ln(1 + k·v) / ln(1 + k)`, expanding shadow detail.
+    Log,
+    /// The inverse of [`Companding::Log`], compressing shadows.
+    Exp,
+}
+
+impl Companding {
+    pub fn apply(self, value: f32) -> f32 {
+        let k = COMPANDING_STRENGTH;
+        let value = value.clamp(0.0, 1.0);
+        match self {
+            Companding::None => value,
+            Companding::Log => (1.0 + k * value).ln() / (1.0 + k).ln(),
+            Companding::Exp => ((1.0 + k).powf(value) - 1.0) / k,
+        }
+    }
+}
+
+/// Apply `companding` to every value.
+pub fn apply_companding(values: &mut [f32], companding: Companding) {
+    if companding == Companding::None {
+        return;
+    }
+
+    for value in values {
+        *value = companding.apply(*value);
+    }
+}
+
+pub fn extract_luma(image: &DynamicImage, invert: bool, tone_map: ToneMap) -> Vec<f32> {
     let gray = image.to_luma32f();
     let mut data = Vec::with_capacity((gray.width() * gray.height()) as usize);
     for pixel in gray.pixels() {
-        let mut lum = pixel.0[0];
+        let mut lum = tone_map.apply(pixel.0[0]);
         if invert {
             lum = 1.0 - lum;
         }
-        data.push(lum.clamp(0.0, 1.0));
+        data.push(lum);
     }
     data
 }
@@ -28,3 +91,473 @@ pub fn apply_contrast_and_brightness(values: &mut [f32], contrast: f32, brightne
         *value = v.clamp(0.0, 1.0);
     }
 }
+
+/// Percentiles stretched to black and white by [`auto_levels`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AutoLevels {
+    /// Percentage of values, from 0 to 100, that end up black.
+    pub low: f32,
+    /// Percentage of values, from 0 to 100, that end up below white.
+    pub high: f32,
+}
+
+impl Default for AutoLevels {
+    fn default() -> Self {
+        Self { low: 2.0, high: 98.0 }
+    }
+}
+
+/// Stretch values so the `low_pct` percentile becomes 0 and the `high_pct` percentile becomes 1,
+/// clipping those outside.
+///
+/// Percentiles are given from 0 to 100 and looked up in a histogram of the clamped values. When
+/// both percentiles fall on the same value, as in flat images, the values are left unchanged.
+pub fn auto_levels(values: &mut [f32], low_pct: f32, high_pct: f32) {
+    const BINS: usize = 256;
+
+    if values.is_empty() {
+        return;
+    }
+
+    // Extremes of each bin, so the percentiles land on real values instead of bin edges.
+    let mut counts = [0usize; BINS];
+    let mut extremes = [(f32::INFINITY, f32::NEG_INFINITY); BINS];
+    for &value in values.iter() {
+        let value = value.clamp(0.0, 1.0);
+        let bin = ((value * BINS as f32) as usize).min(BINS - 1);
+        counts[bin] += 1;
+        extremes[bin] = (extremes[bin].0.min(value), extremes[bin].1.max(value));
+    }
+
+    let percentile_bin = |pct: f32| {
+        let rank = (pct.clamp(0.0, 100.0) / 100.0 * values.len() as f32).ceil().max(1.0) as usize;
+        let mut seen = 0;
+        counts
+            .iter()
+            .position(|&count| {
+                seen += count;
+                seen >= rank
+            })
+            .unwrap_or(BINS - 1)
+    };
+    let low = extremes[percentile_bin(low_pct.min(high_pct))].0;
+    let high = extremes[percentile_bin(high_pct.max(low_pct))].1;
+    if high - low <= f32::EPSILON {
+        return;
+    }
+
+    for value in values {
+        *value = ((*value - low) / (high - low)).clamp(0.0, 1.0);
+    }
+}
+
+/// Raise every value, clamped to `[0, 1]`, to the power of `1 / gamma`.
+///
+/// Gammas above 1 lift the midtones and below 1 darken them, while 0 and 1 stay in place. A
+/// gamma of zero or less is treated as 1 and leaves the values unchanged.
+pub fn apply_gamma(values: &mut [f32], gamma: f32) {
+    if gamma.is_nan() || gamma <= 0.0 || gamma == 1.0 {
+        return;
+    }
+
+    let exponent = 1.0 / gamma;
+    for value in values {
+        *value = value.clamp(0.0, 1.0).powf(exponent);
+    }
+}
+
+/// Replace each value with the median of its `(2 * radius + 1)²` neighborhood.
+///
+/// Sample coordinates outside the buffer are clamped to the nearest border value.
+pub fn median_filter(values: &mut [f32], width: u16, height: u16, radius: u8) {
+    let (width, height) = (width as usize, height as usize);
+    if radius == 0 || width == 0 || height == 0 {
+        return;
+    }
+
+    let radius = radius as isize;
+    let source = values.to_vec();
+    let mut window = Vec::with_capacity((2 * radius as usize + 1).pow(2));
+
+    for y in 0..height {
+        for x in 0..width {
+            window.clear();
+            for dy in -radius..=radius {
+                let sy = (y as isize + dy).clamp(0, height as isize - 1) as usize;
+                for dx in -radius..=radius {
+                    let sx = (x as isize + dx).clamp(0, width as isize - 1) as usize;
+                    window.push(source[sy * width + sx]);
+                }
+            }
+
+            let middle = window.len() / 2;
+            let (_, median, _) = window.select_nth_unstable_by(middle, f32::total_cmp);
+            values[y * width + x] = *median;
+        }
+    }
+}
+
+/// Smooth values with a Gaussian of standard deviation `sigma` cells, applied as a horizontal and
+/// then a vertical pass.
+///
+/// The kernel reaches `3 * sigma` cells to each side, and sample coordinates outside the buffer
+/// are clamped to the nearest border value. A `sigma` of zero or less leaves the values as they
+/// are.
+pub fn gaussian_blur(values: &mut [f32], width: u16, height: u16, sigma: f32) {
+    let (width, height) = (width as usize, height as usize);
+    if width == 0 || height == 0 || sigma.is_nan() || sigma <= 0.0 {
+        return;
+    }
+
+    let radius = (3.0 * sigma).ceil() as isize;
+    let factor = -0.5 / (sigma * sigma);
+    let mut kernel: Vec<f32> =
+        (-radius..=radius).map(|d| ((d * d) as f32 * factor).exp()).collect();
+    let total: f32 = kernel.iter().sum();
+    kernel.iter_mut().for_each(|weight| *weight /= total);
+
+    let blur = |source: &[f32], target: &mut [f32], len: usize, index: &dyn Fn(usize) -> usize| {
+        for position in 0..len {
+            target[index(position)] = kernel
+                .iter()
+                .zip(-radius..=radius)
+                .map(|(weight, d)| {
+                    let sample = (position as isize + d).clamp(0, len as isize - 1) as usize;
+                    weight * source[index(sample)]
+                })
+                .sum();
+        }
+    };
+
+    let mut rows = vec![0.0; values.len()];
+    for y in 0..height {
+        blur(values, &mut rows, width, &|x| y * width + x);
+    }
+    for x in 0..width {
+        blur(&rows, values, height, &|y| y * width + x);
+    }
+}
+
+/// Parameters for [`bilateral_filter`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bilateral {
+    /// Standard deviation of the spatial Gaussian, in cells.
+    pub spatial_sigma: f32,
+    /// Standard deviation of the range Gaussian, in luminance units.
+    pub range_sigma: f32,
+}
+
+/// Smooth values with neighbors of similar value, leaving strong edges intact.
+///
+/// Each neighbor within `2 * spatial_sigma` cells is weighted by both its distance and its
+/// difference in value; the window is cropped at the borders. Values are left untouched unless
+/// both sigmas are positive and finite.
+pub fn bilateral_filter(
+    values: &mut [f32],
+    width: u16,
+    height: u16,
+    spatial_sigma: f32,
+    range_sigma: f32,
+) {
+    let (width, height) = (width as usize, height as usize);
+    let valid = |sigma: f32| sigma > 0.0 && sigma.is_finite();
+    if width == 0 || height == 0 || !valid(spatial_sigma) || !valid(range_sigma) {
+        return;
+    }
+
+    let radius = (2.0 * spatial_sigma).ceil() as isize;
+    let spatial_factor = -0.5 / (spatial_sigma * spatial_sigma);
+    let range_factor = -0.5 / (range_sigma * range_sigma);
+    let source = values.to_vec();
+
+    for y in 0..height {
+        for x in 0..width {
+            let center = source[y * width + x];
+            let (mut sum, mut weights) = (0.0, 0.0);
+            for dy in -radius..=radius {
+                let sy = y as isize + dy;
+                if sy < 0 || sy >= height as isize {
+                    continue;
+                }
+                for dx in -radius..=radius {
+                    let sx = x as isize + dx;
+                    if sx < 0 || sx >= width as isize {
+                        continue;
+                    }
+
+                    let value = source[sy as usize * width + sx as usize];
+                    let distance = (dx * dx + dy * dy) as f32;
+                    let difference = value - center;
+                    let weight =
+                        (distance * spatial_factor + difference * difference * range_factor).exp();
+                    sum += value * weight;
+                    weights += weight;
+                }
+            }
+            values[y * width + x] = sum / weights;
+        }
+    }
+}
+
+/// Parameters for [`adaptive_threshold`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AdaptiveThreshold {
+    /// Side length of the window the local mean is computed over.
+    pub block: u16,
+    /// Offset subtracted from the local mean before comparing.
+    pub c: f32,
+}
+
+/// Binarize each value against the mean of its surrounding `block×block` window minus `c`.
+///
+/// Values above the threshold become 1.0, all others 0.0. Windows are cropped at the borders.
+pub fn adaptive_threshold(values: &mut [f32], width: u16, height: u16, block: u16, c: f32) {
+    let (width, height) = (width as usize, height as usize);
+    if width == 0 || height == 0 {
+        return;
+    }
+
+    // Summed-area table with an extra leading row and column of zeros.
+    let stride = width + 1;
+    let mut integral = vec![0.0f64; stride * (height + 1)];
+    for y in 0..height {
+        let mut row_sum = 0.0;
+        for x in 0..width {
+            row_sum += values[y * width + x] as f64;
+            integral[(y + 1) * stride + x + 1] = integral[y * stride + x + 1] + row_sum;
+        }
+    }
+
+    let before = (block.max(1) as usize - 1) / 2;
+    let after = block.max(1) as usize / 2;
+    for y in 0..height {
+        let (top, bottom) = (y.saturating_sub(before), (y + after + 1).min(height));
+        for x in 0..width {
+            let (left, right) = (x.saturating_sub(before), (x + after + 1).min(width));
+            let sum = integral[bottom * stride + right]
+                - integral[top * stride + right]
+                - integral[bottom * stride + left]
+                + integral[top * stride + left];
+            let mean = sum / ((bottom - top) * (right - left)) as f64;
+
+            let value = &mut values[y * width + x];
+            *value = if *value as f64 > mean - c as f64 { 1.0 } else { 0.0 };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_companding_lifts_shadows() {
+        let log = Companding::Log;
+        assert_eq!(0.0, log.apply(0.0));
+        assert!((log.apply(1.0) - 1.0).abs() < 1e-6);
+        assert!(log.apply(0.1) - 0.1 > log.apply(0.9) - 0.9);
+        assert!(log.apply(0.1) > 0.1);
+
+        let exp = Companding::Exp;
+        assert!(exp.apply(0.1) < 0.1);
+        assert!((exp.apply(log.apply(0.3)) - 0.3).abs() < 1e-5);
+    }
+
+    #[test]
+    fn auto_levels_stretches_percentiles() {
+        // A washed out ramp from 0.3 to 0.7 with a single dark and bright outlier.
+        let mut values: Vec<f32> = (0..98).map(|index| 0.3 + 0.4 * index as f32 / 97.0).collect();
+        values.extend([0.0, 1.0]);
+
+        auto_levels(&mut values, 2.0, 98.0);
+
+        assert_eq!(0.0, values[0]);
+        assert_eq!(1.0, values[97]);
+        assert!((values[49] - 0.5).abs() < 0.02, "{}", values[49]);
+        assert_eq!([0.0, 1.0], values[98..]);
+    }
+
+    #[test]
+    fn auto_levels_keeps_flat_images() {
+        let mut values = vec![0.42; 16];
+
+        auto_levels(&mut values, 2.0, 98.0);
+
+        assert_eq!(vec![0.42; 16], values);
+    }
+
+    #[test]
+    fn gamma_lifts_midtones() {
+        let mut values = vec![0.0, 0.2, 0.5, 0.8, 1.0];
+
+        apply_gamma(&mut values, 2.2);
+
+        assert_eq!(0.0, values[0]);
+        assert_eq!(1.0, values[4]);
+        assert!((values[2] - 0.5f32.powf(1.0 / 2.2)).abs() < 1e-6);
+        assert!(values[1] > 0.2 && values[2] > 0.5 && values[3] > 0.8);
+        assert!(values.windows(2).all(|pair| pair[0] < pair[1]), "{values:?}");
+
+        for gamma in [0.0, -2.2] {
+            let mut unchanged = vec![0.0, 0.2, 0.5, 0.8, 1.0];
+            apply_gamma(&mut unchanged, gamma);
+            assert_eq!(vec![0.0, 0.2, 0.5, 0.8, 1.0], unchanged);
+        }
+    }
+
+    #[test]
+    fn median_removes_outlier() {
+        let mut values = vec![0.5; 25];
+        values[12] = 1.0;
+
+        median_filter(&mut values, 5, 5, 1);
+
+        assert!(values.iter().all(|&value| value == 0.5));
+    }
+
+    #[test]
+    fn gaussian_blur_spreads_impulse() {
+        let mut values = vec![0.0; 81];
+        values[40] = 1.0;
+
+        gaussian_blur(&mut values, 9, 9, 1.0);
+
+        assert!((values.iter().sum::<f32>() - 1.0).abs() < 1e-5);
+        assert!(values[40] > values[41] && values[41] > values[42] && values[42] > 0.0);
+        assert_eq!(values[39], values[41]);
+        assert_eq!(values[31], values[41]);
+        assert_eq!(values[30], values[50]);
+    }
+
+    #[test]
+    fn gaussian_blur_clamps_borders() {
+        let ramp: Vec<f32> = (0..20).map(|index| if index % 5 == 0 { 1.0 } else { 0.5 }).collect();
+
+        let mut flat = vec![0.25; 20];
+        gaussian_blur(&mut flat, 5, 4, 2.0);
+        assert!(flat.iter().all(|&value| (value - 0.25).abs() < 1e-6), "{flat:?}");
+
+        // Clamped samples repeat the bright left column, so it stays the brightest.
+        let mut values = ramp.clone();
+        gaussian_blur(&mut values, 5, 4, 0.8);
+        assert!(values[0] < 1.0 && values[0] > values[1]);
+
+        for sigma in [0.0, -1.0] {
+            let mut values = ramp.clone();
+            gaussian_blur(&mut values, 5, 4, sigma);
+            assert_eq!(ramp, values);
+        }
+    }
+
+    #[test]
+    fn adaptive_threshold_uneven_lighting() {
+        // Page lit from the right with dark vertical text strokes.
+        let (width, height) = (32, 8);
+        let is_text = |x: usize, y: usize| x % 4 == 1 && (2..6).contains(&y);
+        let page: Vec<f32> = (0..width * height)
+            .map(|i| {
+                let (x, y) = (i % width, i / width);
+                let background = 0.2 + 0.7 * x as f32 / (width - 1) as f32;
+                if is_text(x, y) {
+                    background - 0.15
+                } else {
+                    background
+                }
+            })
+            .collect();
+        let expected: Vec<f32> = (0..width * height)
+            .map(|i| if is_text(i % width, i / width) { 0.0 } else { 1.0 })
+            .collect();
+
+        let mut adaptive = page.clone();
+        adaptive_threshold(&mut adaptive, width as u16, height as u16, 7, 0.05);
+        assert_eq!(expected, adaptive);
+
+        let global: Vec<f32> = page.iter().map(|&v| if v > 0.5 { 1.0 } else { 0.0 }).collect();
+        assert_ne!(expected, global);
+    }
+
+    #[test]
+    fn bilateral_smooths_noise_but_keeps_edges() {
+        // Noisy dark and bright halves split by a vertical step edge between columns 7 and 8.
+        let (width, height) = (16, 8);
+        let noisy: Vec<f32> = (0..width * height)
+            .map(|i| {
+                let (x, y) = (i % width, i / width);
+                let base = if x < 8 { 0.2 } else { 0.8 };
+                if (x + y) % 2 == 0 {
+                    base + 0.04
+                } else {
+                    base - 0.04
+                }
+            })
+            .collect();
+
+        let mut filtered = noisy.clone();
+        bilateral_filter(&mut filtered, width as u16, height as u16, 1.5, 0.1);
+
+        let spread = |values: &[f32]| {
+            let patch: Vec<f32> =
+                (0..height).flat_map(|y| values[y * width..][..6].to_vec()).collect();
+            let max = patch.iter().copied().fold(f32::MIN, f32::max);
+            let min = patch.iter().copied().fold(f32::MAX, f32::min);
+            max - min
+        };
+        assert!(spread(&filtered) < spread(&noisy) / 4.0, "{}", spread(&filtered));
+
+        for y in 0..height {
+            let step = filtered[y * width + 8] - filtered[y * width + 7];
+            assert!(step > 0.5, "row {y}: {step}");
+        }
+    }
+
+    #[test]
+    fn bilateral_ignores_invalid_sigmas() {
+        let values: Vec<f32> = (0..16).map(|i| (i % 3) as f32 / 2.0).collect();
+        for (spatial_sigma, range_sigma) in [(f32::NAN, 0.1), (1.5, f32::NAN), (f32::INFINITY, 0.1)]
+        {
+            let mut filtered = values.clone();
+            bilateral_filter(&mut filtered, 4, 4, spatial_sigma, range_sigma);
+            assert_eq!(values, filtered);
+        }
+    }
+
+    #[test]
+    fn median_preserves_edges() {
+        // Vertical step edge between columns 2 and 3.
+        let step: Vec<f32> = (0..36).map(|i| if i % 6 < 3 { 0.0 } else { 1.0 }).collect();
+
+        let mut median = step.clone();
+        median_filter(&mut median, 6, 6, 1);
+
+        // 3x3 box blur with clamped borders for comparison.
+        let clamp = |v: isize| v.clamp(0, 5) as usize;
+        let blurred: Vec<f32> = (0..36isize)
+            .map(|i| {
+                let (x, y) = (i % 6, i / 6);
+                let mut sum = 0.0;
+                for dy in -1..=1 {
+                    for dx in -1..=1 {
+                        sum += step[clamp(y + dy) * 6 + clamp(x + dx)];
+                    }
+                }
+                sum / 9.0
+            })
+            .collect();
+
+        assert_eq!(step, median);
+        let step_height = |values: &[f32]| values[3] - values[2];
+        assert!(step_height(&median) > step_height(&blurred));
+    }
+
+    #[test]
+    fn reinhard_keeps_highlights_apart() {
+        let (bright, brighter) = (2.0, 4.0);
+
+        assert_eq!(ToneMap::None.apply(bright), ToneMap::None.apply(brighter));
+        let (a, b) = (ToneMap::Reinhard.apply(bright), ToneMap::Reinhard.apply(brighter));
+        assert!(a < b && b < 1.0, "{a} {b}");
+        let (a, b) = (ToneMap::Aces.apply(bright), ToneMap::Aces.apply(brighter));
+        assert!(a < b && b <= 1.0, "{a} {b}");
+    }
+}
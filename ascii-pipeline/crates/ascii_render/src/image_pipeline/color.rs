@@ -0,0 +1,108 @@
+/// The four colors of the original Game Boy screen, dark to light.
+pub const GAME_BOY: &[[u8; 3]] = &[[15, 56, 15], [48, 98, 48], [139, 172, 15], [155, 188, 15]];
+
+/// CGA palette 1 in high intensity: black, cyan, magenta and white.
+pub const CGA: &[[u8; 3]] = &[[0, 0, 0], [85, 255, 255], [255, 85, 255], [255, 255, 255]];
+
+/// Index of the palette entry closest to `color` by Euclidean distance in RGB.
+///
+/// Returns `None` for an empty palette.
+pub fn nearest_color(color: [u8; 3], palette: &[[u8; 3]]) -> Option<usize> {
+    let distance = |entry: &[u8; 3]| -> u32 {
+        color.iter().zip(entry).map(|(&a, &b)| (a as i32 - b as i32).pow(2) as u32).sum()
+    };
+
+    palette.iter().enumerate().min_by_key(|(_, entry)| distance(entry)).map(|(index, _)| index)
+}
+
+/// Snap every pixel of an RGBA buffer to its nearest palette entry, leaving alpha untouched.
+pub fn quantize_to_palette(rgba: &mut [u8], palette: &[[u8; 3]]) {
+    for pixel in rgba.chunks_exact_mut(4) {
+        if let Some(index) = nearest_color([pixel[0], pixel[1], pixel[2]], palette) {
+            pixel[..3].copy_from_slice(&palette[index]);
+        }
+    }
+}
+
+/// Warm (positive) or cool (negative) an RGBA buffer by scaling its red and blue channels.
+///
+/// Each 1000 of `kelvin_shift` moves red and blue 10% in opposite directions, up to doubling one
+/// and removing the other at ±10000.
+pub fn adjust_temperature(rgba: &mut [u8], kelvin_shift: f32) {
+    let shift = (kelvin_shift / 10_000.0).clamp(-1.0, 1.0);
+    if shift == 0.0 {
+        return;
+    }
+
+    let scale =
+        |channel: u8, factor: f32| (channel as f32 * factor).round().clamp(0.0, 255.0) as u8;
+    for pixel in rgba.chunks_exact_mut(4) {
+        pixel[0] = scale(pixel[0], 1.0 + shift);
+        pixel[2] = scale(pixel[2], 1.0 - shift);
+    }
+}
+
+/// Offset the red, green and blue channels of an RGBA buffer independently.
+///
+/// Offsets are in channel units, so `255.0` saturates a channel; results are clamped to
+/// `[0, 255]` and alpha is left untouched.
+pub fn apply_channel_brightness(rgba: &mut [u8], r: f32, g: f32, b: f32) {
+    if r == 0.0 && g == 0.0 && b == 0.0 {
+        return;
+    }
+
+    let offset =
+        |channel: u8, offset: f32| (channel as f32 + offset).round().clamp(0.0, 255.0) as u8;
+    for pixel in rgba.chunks_exact_mut(4) {
+        pixel[0] = offset(pixel[0], r);
+        pixel[1] = offset(pixel[1], g);
+        pixel[2] = offset(pixel[2], b);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearest_palette_entry() {
+        assert_eq!(Some(0), nearest_color([10, 10, 10], CGA));
+        assert_eq!(Some(1), nearest_color([0, 200, 220], CGA));
+        assert_eq!(Some(3), nearest_color([250, 240, 250], CGA));
+        assert_eq!(None, nearest_color([0, 0, 0], &[]));
+    }
+
+    #[test]
+    fn quantize_keeps_alpha() {
+        let mut rgba = vec![200, 10, 190, 128, 0, 0, 0, 255];
+
+        quantize_to_palette(&mut rgba, CGA);
+
+        assert_eq!(vec![255, 85, 255, 128, 0, 0, 0, 255], rgba);
+    }
+
+    #[test]
+    fn warm_shift_favors_red() {
+        let mut rgba = vec![100, 100, 100, 255, 40, 80, 160, 255];
+        let average = |rgba: &[u8], channel: usize| {
+            rgba.chunks_exact(4).map(|pixel| pixel[channel] as u32).sum::<u32>() / 2
+        };
+        let balance = average(&rgba, 0) as i32 - average(&rgba, 2) as i32;
+
+        adjust_temperature(&mut rgba, 2000.0);
+
+        assert!(average(&rgba, 0) as i32 - average(&rgba, 2) as i32 > balance);
+        assert_eq!(vec![120, 100, 80, 255, 48, 80, 128, 255], rgba);
+    }
+
+    #[test]
+    fn red_offset_leaves_other_channels() {
+        let mut rgba = vec![100, 100, 100, 255, 250, 0, 30, 128];
+
+        apply_channel_brightness(&mut rgba, 20.0, 0.0, 0.0);
+        assert_eq!(vec![120, 100, 100, 255, 255, 0, 30, 128], rgba);
+
+        apply_channel_brightness(&mut rgba, 0.0, -10.0, 5.5);
+        assert_eq!(vec![120, 90, 106, 255, 255, 0, 36, 128], rgba);
+    }
+}
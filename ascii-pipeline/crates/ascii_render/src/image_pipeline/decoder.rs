@@ -0,0 +1,146 @@
+use image::DynamicImage;
+
+use crate::AsciiError;
+
+/// Turns encoded image bytes into pixels.
+///
+/// Implement this to render formats the `image` crate doesn't support, like DICOM or camera RAW
+/// files, through [`AsciiRenderer::render_bytes_with`](crate::AsciiRenderer::render_bytes_with).
+pub trait Decoder {
+    fn decode(&self, bytes: &[u8]) -> Result<DynamicImage, AsciiError>;
+}
+
+/// Decoder for the formats enabled in the `image` crate, detected from the data itself.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ImageCrateDecoder;
+
+impl Decoder for ImageCrateDecoder {
+    fn decode(&self, bytes: &[u8]) -> Result<DynamicImage, AsciiError> {
+        #[cfg(feature = "jpeg")]
+        if let Some(image) = decode_cmyk_jpeg(bytes)? {
+            return Ok(image);
+        }
+
+        Ok(image::load_from_memory(bytes)?)
+    }
+}
+
+/// Decode `bytes` if they hold a CMYK JPEG, converting it to RGB.
+///
+/// The `image` crate assumes every CMYK JPEG stores inverted ink values like Adobe's encoders do,
+/// which turns files without an Adobe marker into negatives.
+#[cfg(feature = "jpeg")]
+fn decode_cmyk_jpeg(bytes: &[u8]) -> Result<Option<DynamicImage>, AsciiError> {
+    use image::error::{DecodingError, ImageFormatHint};
+    use image::{ImageError, ImageFormat, RgbImage};
+
+    if image::guess_format(bytes).ok() != Some(ImageFormat::Jpeg) {
+        return Ok(None);
+    }
+
+    let error = |err| {
+        let hint = ImageFormatHint::Exact(ImageFormat::Jpeg);
+        AsciiError::Image(ImageError::Decoding(DecodingError::new(hint, err)))
+    };
+    let mut decoder = jpeg_decoder::Decoder::new(bytes);
+    decoder.read_info().map_err(error)?;
+    let info = match decoder.info() {
+        Some(info) if info.pixel_format == jpeg_decoder::PixelFormat::CMYK32 => info,
+        _ => return Ok(None),
+    };
+    let pixels = decoder.decode().map_err(error)?;
+
+    // The decoder inverts all CMYK data, which only yields ink values for Adobe files.
+    let adobe = has_adobe_marker(bytes);
+    let ink = |value: u8| u16::from(if adobe { value } else { 255 - value });
+    let rgb = pixels
+        .chunks_exact(4)
+        .flat_map(|cmyk| {
+            let white = 255 - ink(cmyk[3]);
+            [0, 1, 2].map(|channel| ((255 - ink(cmyk[channel])) * white / 255) as u8)
+        })
+        .collect();
+
+    let image = RgbImage::from_raw(u32::from(info.width), u32::from(info.height), rgb);
+    Ok(image.map(DynamicImage::ImageRgb8))
+}
+
+/// Whether the JPEG in `bytes` has an Adobe APP14 segment before its image data.
+#[cfg(feature = "jpeg")]
+fn has_adobe_marker(bytes: &[u8]) -> bool {
+    // Skip the start of image marker.
+    let mut offset = 2;
+    while let [0xff, marker, high, low, ..] = bytes[offset.min(bytes.len())..] {
+        // Image data follows the start of scan marker.
+        if marker == 0xda || marker == 0xd9 {
+            break;
+        }
+
+        let length = usize::from(u16::from_be_bytes([high, low]));
+        let segment = &bytes[(offset + 4).min(bytes.len())..(offset + 2 + length).min(bytes.len())];
+        if marker == 0xee && segment.starts_with(b"Adobe") {
+            return true;
+        }
+        offset += 2 + length;
+    }
+    false
+}
+
+#[cfg(all(test, feature = "jpeg"))]
+mod tests {
+    use super::*;
+
+    /// 8x8 baseline JPEG with four components, each a single flat block at `value` (0 or 255).
+    fn cmyk_jpeg(value: u8, adobe: bool) -> Vec<u8> {
+        let mut jpeg = vec![0xff, 0xd8];
+        if adobe {
+            // Version 100, no flags, no color transform.
+            jpeg.extend_from_slice(&[0xff, 0xee, 0, 14]);
+            jpeg.extend_from_slice(b"Adobe");
+            jpeg.extend_from_slice(&[0, 100, 0, 0, 0, 0, 0]);
+        }
+
+        // Every coefficient is quantized by 128, so a DC of ±8 saturates the block.
+        jpeg.extend_from_slice(&[0xff, 0xdb, 0, 67, 0]);
+        jpeg.extend_from_slice(&[128; 64]);
+
+        jpeg.extend_from_slice(&[0xff, 0xc0, 0, 20, 8, 0, 8, 0, 8, 4]);
+        for id in 1..=4 {
+            jpeg.extend_from_slice(&[id, 0x11, 0]);
+        }
+
+        // DC table with only category 4 and AC table with only end of block, both coded as `0`.
+        for (class, symbol) in [(0x00, 4), (0x10, 0)] {
+            jpeg.extend_from_slice(&[0xff, 0xc4, 0, 20, class, 1]);
+            jpeg.extend_from_slice(&[0; 15]);
+            jpeg.push(symbol);
+        }
+
+        jpeg.extend_from_slice(&[0xff, 0xda, 0, 14, 4]);
+        for id in 1..=4 {
+            jpeg.extend_from_slice(&[id, 0]);
+        }
+        jpeg.extend_from_slice(&[0, 63, 0]);
+
+        // Per block: DC code `0`, magnitude bits for +8 (`1000`) or -8 (`0111`), then EOB `0`.
+        let block = if value == 255 { 0b010000 } else { 0b001110 };
+        let bits: u32 = (0..4).fold(0, |bits, _| (bits << 6) | block);
+        jpeg.extend_from_slice(&bits.to_be_bytes()[1..]);
+
+        jpeg.extend_from_slice(&[0xff, 0xd9]);
+        jpeg
+    }
+
+    #[test]
+    fn cmyk_jpeg_is_not_inverted() {
+        // No ink is white: Adobe files store it inverted, other files as is.
+        for (value, adobe) in [(255, true), (0, false)] {
+            let jpeg = cmyk_jpeg(value, adobe);
+            assert_eq!(adobe, has_adobe_marker(&jpeg));
+
+            let image = ImageCrateDecoder.decode(&jpeg).unwrap().into_luma8();
+            assert_eq!((8, 8), image.dimensions());
+            assert!(image.pixels().all(|pixel| pixel.0[0] > 250), "adobe: {adobe}");
+        }
+    }
+}
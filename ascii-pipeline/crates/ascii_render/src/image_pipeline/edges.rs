@@ -1,12 +1,58 @@
-use std::cmp::{max, min};
-
 #[derive(Clone, Copy, Debug)]
 pub enum EdgeMode {
     None,
-    Sobel { threshold: f32 },
+    Sobel {
+        threshold: f32,
+        /// Glyph drawn on every edge above the threshold, instead of one picked by magnitude.
+        edge_glyph: Option<char>,
+        /// Color of the glyphs on edges above the threshold.
+        color: EdgeColorSource,
+        /// Handling of cells on the border of the grid.
+        border: BorderMode,
+        /// Radius in cells by which edges are thickened, see [`dilate`].
+        dilate: u8,
+        /// Gradient operator, Sobel unless another is picked.
+        kernel: EdgeKernel,
+    },
+    /// Sobel edges drawn as `- / | \` line glyphs following their direction, see
+    /// [`sobel_orientation`].
+    SobelOriented {
+        threshold: f32,
+        /// Handling of cells on the border of the grid.
+        border: BorderMode,
+        /// Gradient operator, Sobel unless another is picked.
+        kernel: EdgeKernel,
+    },
+    /// The luminance shading with Sobel edges drawn over it, see [`overlay`].
+    SobelOverlay {
+        threshold: f32,
+        /// How far, from 0 to 1, edges above the threshold pull their cell toward the gradient's
+        /// first, densest glyph.
+        strength: f32,
+    },
+    /// One cell wide edges found with the Canny detector, see [`canny`].
+    Canny {
+        /// Gradient magnitude below which no edge is kept.
+        low: f32,
+        /// Gradient magnitude above which edges are always kept.
+        high: f32,
+    },
+}
+
+/// Where the color of edge glyphs comes from.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EdgeColorSource {
+    /// The source image's color under the edge, so outlines keep their object's color.
+    #[default]
+    Source,
+    /// The same color for every edge.
+    Fixed([u8; 3]),
+    /// A gray as bright as the edge is strong.
+    Magnitude,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct EdgeSample {
     pub active: bool,
     pub magnitude: f32,
@@ -18,68 +64,483 @@ pub enum EdgeResult {
     Orientation(Vec<EdgeSample>),
 }
 
-pub fn sobel_map(values: &[f32], width: u16, height: u16, threshold: f32) -> Vec<f32> {
+/// How Sobel filters treat values on the border of the buffer, which lack neighbors on one side.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BorderMode {
+    /// Leave border values without an edge.
+    Zero,
+    /// Repeat the outermost values past the border.
+    #[default]
+    Clamp,
+    /// Mirror the values next to the border.
+    Reflect,
+}
+
+impl BorderMode {
+    /// Index inside `0..len` of `position`, which may lie one past either end.
+    fn resolve(self, position: isize, len: usize) -> usize {
+        let last = len as isize - 1;
+        let position = match self {
+            BorderMode::Reflect if position < 0 => -position,
+            BorderMode::Reflect if position > last => 2 * last - position,
+            _ => position,
+        };
+        position.clamp(0, last) as usize
+    }
+}
+
+/// A 3x3 convolution kernel, indexed by row and then column.
+pub type Kernel = [[f32; 3]; 3];
+
+/// Gradient operator used to find edges.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EdgeKernel {
+    #[default]
+    Sobel,
+    /// Uniform weights, slightly noisier than Sobel.
+    Prewitt,
+    /// Heavier center weights, responding more evenly to edges of every direction.
+    Scharr,
+}
+
+impl EdgeKernel {
+    /// Horizontal and vertical gradient kernels of the operator.
+    pub fn kernels(self) -> (Kernel, Kernel) {
+        let (side, center) = match self {
+            EdgeKernel::Sobel => (1.0, 2.0),
+            EdgeKernel::Prewitt => (1.0, 1.0),
+            EdgeKernel::Scharr => (3.0, 10.0),
+        };
+        let kernel_x = [[-side, 0.0, side], [-center, 0.0, center], [-side, 0.0, side]];
+        let kernel_y = [[-side, -center, -side], [0.0; 3], [side, center, side]];
+        (kernel_x, kernel_y)
+    }
+}
+
+pub fn sobel_map(
+    values: &[f32],
+    width: u16,
+    height: u16,
+    threshold: f32,
+    border: BorderMode,
+) -> Vec<f32> {
+    let (kernel_x, kernel_y) = EdgeKernel::Sobel.kernels();
+    convolve_edges(values, width, height, kernel_x, kernel_y, threshold, border)
+}
+
+/// Gradient magnitude of every value in a `width`x`height` buffer, found with a pair of 3x3
+/// kernels such as those of [`EdgeKernel::kernels`].
+///
+/// Magnitudes are normalized by the kernels' total positive weight and clamped to `[0, 1]`;
+/// values weaker than `threshold` become zero.
+pub fn convolve_edges(
+    values: &[f32],
+    width: u16,
+    height: u16,
+    kernel_x: Kernel,
+    kernel_y: Kernel,
+    threshold: f32,
+    border: BorderMode,
+) -> Vec<f32> {
+    let threshold = threshold.clamp(0.0, 1.0);
+    gradients(values, width, height, &kernel_x, &kernel_y, border)
+        .into_iter()
+        .map(|(gx, gy)| {
+            let magnitude = (gx * gx + gy * gy).sqrt().clamp(0.0, 1.0);
+            if magnitude >= threshold {
+                magnitude
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
+
+/// Gradient strength and direction of every value, found with `kernel`.
+///
+/// Magnitudes and angles are those of [`sobel_with_angle`]; samples weaker than `threshold` are
+/// inactive.
+pub fn sobel_orientation(
+    values: &[f32],
+    width: u16,
+    height: u16,
+    kernel: EdgeKernel,
+    threshold: f32,
+    border: BorderMode,
+) -> Vec<EdgeSample> {
+    let threshold = threshold.clamp(0.0, 1.0);
+    let (magnitudes, angles) = edge_angles(values, width, height, kernel, border);
+    magnitudes
+        .into_iter()
+        .zip(angles)
+        .map(|(magnitude, angle_degrees)| EdgeSample {
+            active: magnitude > 0.0 && magnitude >= threshold,
+            magnitude,
+            angle_degrees,
+        })
+        .collect()
+}
+
+/// Sobel gradient magnitude and edge direction of every value in a `width`x`height` buffer.
+///
+/// Magnitudes are normalized like [`sobel_map`]. Angles give the direction the edge runs in,
+/// counter-clockwise from the positive x axis in `[0, 180)` degrees, so vertical edges are at
+/// 90°. `border` decides how values without neighbors on one side are handled.
+pub fn sobel_with_angle(
+    values: &[f32],
+    width: u16,
+    height: u16,
+    border: BorderMode,
+) -> (Vec<f32>, Vec<f32>) {
+    edge_angles(values, width, height, EdgeKernel::Sobel, border)
+}
+
+/// Like [`sobel_with_angle`], with any kernel.
+fn edge_angles(
+    values: &[f32],
+    width: u16,
+    height: u16,
+    kernel: EdgeKernel,
+    border: BorderMode,
+) -> (Vec<f32>, Vec<f32>) {
+    let (kernel_x, kernel_y) = kernel.kernels();
+    gradients(values, width, height, &kernel_x, &kernel_y, border)
+        .into_iter()
+        .map(|(gx, gy)| {
+            let magnitude = (gx * gx + gy * gy).sqrt().clamp(0.0, 1.0);
+            // Image rows grow downwards, and edges run perpendicular to the gradient.
+            let angle = ((-gy).atan2(gx).to_degrees() + 90.0).rem_euclid(180.0);
+            (magnitude, angle)
+        })
+        .unzip()
+}
+
+/// Canny edges of a `width`x`height` buffer, as 1.0 on edges and 0.0 elsewhere.
+///
+/// Sobel gradient magnitudes are thinned to the local maxima along the gradient direction. Of
+/// those, magnitudes of at least `high` are edges, and magnitudes of at least `low` are edges
+/// when they connect to one, so faint stretches of a strong line survive while isolated noise
+/// doesn't.
+pub fn canny(values: &[f32], width: u16, height: u16, low: f32, high: f32) -> Vec<f32> {
+    let (kernel_x, kernel_y) = EdgeKernel::Sobel.kernels();
+    let gradients = gradients(values, width, height, &kernel_x, &kernel_y, BorderMode::Clamp);
+    let magnitudes = suppress_non_maxima(&gradients, width, height);
+    hysteresis(&magnitudes, width, height, low, high)
+}
+
+/// Gradient magnitudes that are the local maximum along their gradient, and zero elsewhere.
+fn suppress_non_maxima(gradients: &[(f32, f32)], width: u16, height: u16) -> Vec<f32> {
+    let (width, height) = (width as usize, height as usize);
+    let magnitudes: Vec<f32> =
+        gradients.iter().map(|(gx, gy)| (gx * gx + gy * gy).sqrt().clamp(0.0, 1.0)).collect();
+    let at = |x: isize, y: isize| {
+        if x < 0 || y < 0 || x as usize >= width || y as usize >= height {
+            0.0
+        } else {
+            magnitudes[y as usize * width + x as usize]
+        }
+    };
+
+    let mut thinned = vec![0.0; magnitudes.len()];
+    for y in 0..height {
+        for x in 0..width {
+            let index = y * width + x;
+            let magnitude = magnitudes[index];
+            if magnitude == 0.0 {
+                continue;
+            }
+
+            // Rows grow downwards, so a positive y gradient points at the next row.
+            let (gx, gy) = gradients[index];
+            let angle = gy.atan2(gx).to_degrees().rem_euclid(180.0);
+            let (dx, dy) = match angle {
+                a if !(22.5..157.5).contains(&a) => (1, 0),
+                a if a < 67.5 => (1, 1),
+                a if a < 112.5 => (0, 1),
+                _ => (-1, 1),
+            };
+            // Ties go to the first of two equal cells, so a plateau still gives a thin line.
+            let (x, y) = (x as isize, y as isize);
+            if magnitude >= at(x + dx, y + dy) && magnitude > at(x - dx, y - dy) {
+                thinned[index] = magnitude;
+            }
+        }
+    }
+
+    thinned
+}
+
+/// Keep magnitudes of at least `high`, and those of at least `low` that are connected to them
+/// through their eight neighbors.
+fn hysteresis(magnitudes: &[f32], width: u16, height: u16, low: f32, high: f32) -> Vec<f32> {
+    let (width, height) = (width as usize, height as usize);
+    let high = high.clamp(0.0, 1.0);
+    let low = low.clamp(0.0, high);
+
+    let mut edges = vec![0.0; magnitudes.len()];
+    let mut pending: Vec<usize> = Vec::new();
+    for (index, &magnitude) in magnitudes.iter().enumerate() {
+        if magnitude > 0.0 && magnitude >= high {
+            edges[index] = 1.0;
+            pending.push(index);
+        }
+    }
+
+    while let Some(index) = pending.pop() {
+        let (x, y) = (index % width, index / width);
+        for ny in y.saturating_sub(1)..(y + 2).min(height) {
+            for nx in x.saturating_sub(1)..(x + 2).min(width) {
+                let neighbor = ny * width + nx;
+                if edges[neighbor] == 0.0
+                    && magnitudes[neighbor] > 0.0
+                    && magnitudes[neighbor] >= low
+                {
+                    edges[neighbor] = 1.0;
+                    pending.push(neighbor);
+                }
+            }
+        }
+    }
+
+    edges
+}
+
+/// Darken the `intensities` of cells whose `edges` are non-zero by `strength`, clamped to
+/// `[0, 1]`.
+///
+/// A strength of 0 leaves the intensities untouched and 1 turns every edge into an intensity of
+/// zero, which maps to the first glyph of the gradient.
+pub fn overlay(intensities: &mut [f32], edges: &[f32], strength: f32) {
+    let keep = 1.0 - strength.clamp(0.0, 1.0);
+    for (intensity, &edge) in intensities.iter_mut().zip(edges) {
+        if edge > 0.0 {
+            *intensity *= keep;
+        }
+    }
+}
+
+/// Grow the non-zero values of a `width`x`height` mask into their neighbors within `radius`
+/// cells, so one cell wide edges stay visible at terminal resolution.
+///
+/// Every value becomes the largest one in the square around it.
+pub fn dilate(mask: &mut [f32], width: u16, height: u16, radius: u8) {
+    if radius == 0 {
+        return;
+    }
+
+    let (width, height, radius) = (width as usize, height as usize, radius as usize);
+    let window = |position: usize, len: usize| {
+        position.saturating_sub(radius)..(position + radius + 1).min(len)
+    };
+
+    // The square is separable into a horizontal pass followed by a vertical one.
+    let mut rows = vec![0.0; mask.len()];
+    for y in 0..height {
+        let row = &mask[y * width..(y + 1) * width];
+        for x in 0..width {
+            rows[y * width + x] = row[window(x, width)].iter().copied().fold(0.0, f32::max);
+        }
+    }
+    for y in 0..height {
+        for x in 0..width {
+            mask[y * width + x] =
+                window(y, height).map(|row| rows[row * width + x]).fold(0.0, f32::max);
+        }
+    }
+}
+
+/// Horizontal and vertical gradient of every value, each divided by the total positive weight of
+/// its kernel so a step from 0 to 1 has a gradient of 1.
+fn gradients(
+    values: &[f32],
+    width: u16,
+    height: u16,
+    kernel_x: &Kernel,
+    kernel_y: &Kernel,
+    border: BorderMode,
+) -> Vec<(f32, f32)> {
     let width = width as usize;
     let height = height as usize;
-    let mut output = vec![0.0; values.len()];
-    let threshold = threshold.clamp(0.0, 1.0);
+    let mut gradients = vec![(0.0, 0.0); values.len()];
+
+    let positive_weight = |kernel: &Kernel| {
+        let total: f32 = kernel.iter().flatten().filter(|&&weight| weight > 0.0).sum();
+        if total > 0.0 {
+            total
+        } else {
+            1.0
+        }
+    };
+    let (scale_x, scale_y) = (positive_weight(kernel_x), positive_weight(kernel_y));
+
+    let interior = |position: usize, len: usize| position >= 1 && position + 1 < len;
+    for y in 0..height {
+        for x in 0..width {
+            if border == BorderMode::Zero && !(interior(x, width) && interior(y, height)) {
+                continue;
+            }
 
-    if width < 3 || height < 3 {
-        return output;
+            let (mut gx, mut gy) = (0.0, 0.0);
+            for dy in 0..3 {
+                let row = border.resolve(y as isize + dy as isize - 1, height);
+                for dx in 0..3 {
+                    let column = border.resolve(x as isize + dx as isize - 1, width);
+                    let value = values[row * width + column];
+                    gx += kernel_x[dy][dx] * value;
+                    gy += kernel_y[dy][dx] * value;
+                }
+            }
+            gradients[y * width + x] = (gx / scale_x, gy / scale_y);
+        }
     }
 
-    for y in 1..height - 1 {
-        for x in 1..width - 1 {
-            let idx = y * width + x;
+    gradients
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            let a = values[(y - 1) * width + (x - 1)];
-            let b = values[(y - 1) * width + x];
-            let c = values[(y - 1) * width + (x + 1)];
-            let d = values[y * width + (x - 1)];
-            let f = values[y * width + (x + 1)];
-            let g = values[(y + 1) * width + (x - 1)];
-            let h = values[(y + 1) * width + x];
-            let i = values[(y + 1) * width + (x + 1)];
+    #[test]
+    fn vertical_edge_angle() {
+        let values: Vec<f32> = (0..36).map(|index| if index % 6 < 3 { 0. } else { 1. }).collect();
 
-            let gx = (-1.0 * a) + (1.0 * c) + (-2.0 * d) + (2.0 * f) + (-1.0 * g) + (1.0 * i);
-            let gy = (-1.0 * a) + (-2.0 * b) + (-1.0 * c) + (1.0 * g) + (2.0 * h) + (1.0 * i);
-            let magnitude = (gx * gx + gy * gy).sqrt();
-            let normalized = (magnitude / 4.0).clamp(0.0, 1.0);
-            output[idx] = if normalized >= threshold { normalized } else { 0.0 };
+        let (magnitudes, angles) = sobel_with_angle(&values, 6, 6, BorderMode::Zero);
+
+        for y in 1..5 {
+            for x in 2..4 {
+                assert_eq!(1., magnitudes[y * 6 + x]);
+                assert!((angles[y * 6 + x] - 90.).abs() < 1e-3, "{}", angles[y * 6 + x]);
+            }
         }
+        assert_eq!(0., magnitudes[6 + 1]);
+        assert_eq!(0., magnitudes[2]);
     }
 
-    output
-}
-
-fn sobel_with_angle(data: &[Vec<f32>]) -> (Vec<Vec<f32>>, Vec<Vec<f32>>) {
-    let height = data.len();
-    let width = data[0].len();
-    let mut magnitude = vec![vec![0.0f32; width]; height];
-    let mut angle = vec![vec![0.0f32; width]; height];
-
-    for y in 1..height - 1 {
-        for x in 1..width - 1 {
-            let a = data[y - 1][x - 1];
-            let b = data[y - 1][x];
-            let c = data[y - 1][x + 1];
-            let d = data[y][x - 1];
-            let f = data[y][x + 1];
-            let g = data[y + 1][x - 1];
-            let h = data[y + 1][x];
-            let i = data[y + 1][x + 1];
-
-            let gx = (-1.0 * a) + (1.0 * c) + (-2.0 * d) + (2.0 * f) + (-1.0 * g) + (1.0 * i);
-            let gy = (-1.0 * a) + (-2.0 * b) + (-1.0 * c) + (1.0 * g) + (2.0 * h) + (1.0 * i);
-            let mag = (gx * gx + gy * gy).sqrt();
-            let mut theta = gy.atan2(gx).to_degrees();
-            if theta < 0.0 {
-                theta += 180.0;
+    #[test]
+    fn canny_thins_step() {
+        let values: Vec<f32> = (0..36).map(|index| if index % 6 < 3 { 0. } else { 1. }).collect();
+
+        let edges = canny(&values, 6, 6, 0.1, 0.3);
+
+        for row in edges.chunks(6) {
+            assert_eq!([0., 0., 1., 0., 0., 0.], row);
+        }
+    }
+
+    #[test]
+    fn hysteresis_bridges_weak_diagonal() {
+        // A diagonal with strong ends, a weak middle and a weak stray cell off the line.
+        let mut magnitudes = vec![0.; 49];
+        for (position, magnitude) in [0.9, 0.8, 0.2, 0.15, 0.2, 0.7, 0.9].into_iter().enumerate() {
+            magnitudes[position * 7 + position] = magnitude;
+        }
+        magnitudes[6] = 0.2;
+
+        let edges = hysteresis(&magnitudes, 7, 7, 0.1, 0.5);
+
+        for position in 0..7 {
+            assert_eq!(1., edges[position * 7 + position], "{position}");
+        }
+        assert_eq!(0., edges[6]);
+        assert_eq!(7., edges.iter().sum::<f32>());
+
+        // Without the strong ends, the weak middle is dropped.
+        let edges = hysteresis(&magnitudes, 7, 7, 0.1, 0.95);
+        assert!(edges.iter().all(|&edge| edge == 0.));
+    }
+
+    #[test]
+    fn overlay_strength() {
+        let intensities = [0.2, 0.6, 1.0, 0.8];
+        let edges = [0.0, 0.5, 1.0, 0.0];
+
+        let mut identity = intensities;
+        overlay(&mut identity, &edges, 0.0);
+        assert_eq!(intensities, identity);
+
+        let mut pure = intensities;
+        overlay(&mut pure, &edges, 1.0);
+        assert_eq!([0.2, 0.0, 0.0, 0.8], pure);
+
+        let mut half = intensities;
+        overlay(&mut half, &edges, 0.5);
+        assert_eq!([0.2, 0.3, 0.5, 0.8], half);
+
+        let mut clamped = intensities;
+        overlay(&mut clamped, &edges, 3.0);
+        assert_eq!(pure, clamped);
+        overlay(&mut clamped, &edges, -1.0);
+        assert_eq!(pure, clamped);
+    }
+
+    #[test]
+    fn diagonal_edge_angle() {
+        // Dark in the top left, so the edge runs from the bottom left to the top right.
+        let values: Vec<f32> =
+            (0..25).map(|index| if index % 5 + index / 5 < 4 { 0. } else { 1. }).collect();
+
+        let (_, angles) = sobel_with_angle(&values, 5, 5, BorderMode::Zero);
+
+        assert!((angles[2 * 5 + 2] - 45.).abs() < 1e-3, "{}", angles[2 * 5 + 2]);
+    }
+
+    #[test]
+    fn kernels_on_ramp() {
+        // Brightness rising by 0.1 per column, so every interior value has the same gradient.
+        let values: Vec<f32> = (0..36).map(|index| (index % 6) as f32 / 10.).collect();
+
+        for kernel in [EdgeKernel::Sobel, EdgeKernel::Prewitt, EdgeKernel::Scharr] {
+            let (kernel_x, kernel_y) = kernel.kernels();
+            let magnitudes =
+                convolve_edges(&values, 6, 6, kernel_x, kernel_y, 0.1, BorderMode::Zero);
+            let samples = sobel_orientation(&values, 6, 6, kernel, 0.1, BorderMode::Zero);
+
+            for y in 1..5 {
+                for x in 1..5 {
+                    let index = y * 6 + x;
+                    assert!((magnitudes[index] - 0.2).abs() < 1e-5, "{kernel:?} {magnitudes:?}");
+                    assert!(samples[index].active, "{kernel:?}");
+                    assert!((samples[index].angle_degrees - 90.).abs() < 1e-3, "{kernel:?}");
+                }
             }
-            magnitude[y][x] = mag;
-            angle[y][x] = theta;
+            assert_eq!(0., magnitudes[0], "{kernel:?}");
+            let weak = convolve_edges(&values, 6, 6, kernel_x, kernel_y, 0.5, BorderMode::Zero);
+            assert!(weak.iter().all(|&magnitude| magnitude == 0.), "{kernel:?}");
         }
     }
 
-    (magnitude, angle)
+    #[test]
+    fn dilate_thickens_lines() {
+        let mut mask: Vec<f32> =
+            (0..25).map(|index| if index % 5 == 2 { 1. } else { 0. }).collect();
+
+        dilate(&mut mask, 5, 5, 1);
+
+        for y in 0..5 {
+            assert_eq!([0., 1., 1., 1., 0.], mask[y * 5..(y + 1) * 5], "row {y}");
+        }
+
+        let mut unchanged = vec![0., 0.5, 0.];
+        dilate(&mut unchanged, 3, 1, 0);
+        assert_eq!(vec![0., 0.5, 0.], unchanged);
+    }
+
+    #[test]
+    fn border_modes() {
+        // Bright left column against a dark rest, so the edge touches the left border.
+        let values: Vec<f32> = (0..16).map(|index| if index % 4 == 0 { 1. } else { 0. }).collect();
+        let left_column = |border| {
+            let magnitudes = sobel_map(&values, 4, 4, 0.1, border);
+            (0..4).map(|y| magnitudes[y * 4]).collect::<Vec<_>>()
+        };
+
+        assert!(left_column(BorderMode::Clamp).iter().all(|&magnitude| magnitude == 1.));
+        assert_eq!(vec![0.; 4], left_column(BorderMode::Zero));
+        // Mirroring around the border column puts dark values on both of its sides.
+        assert_eq!(vec![0.; 4], left_column(BorderMode::Reflect));
+
+        let interior = |border| sobel_map(&values, 4, 4, 0.1, border)[5];
+        assert_eq!(interior(BorderMode::Zero), interior(BorderMode::Clamp));
+    }
 }
@@ -1,27 +1,237 @@
-use image::DynamicImage;
-use image::GenericImageView;
+use std::collections::VecDeque;
+#[cfg(feature = "gif")]
+use std::io::{Cursor, Read};
+use std::time::Duration;
+
+#[cfg(feature = "gif")]
+use image::AnimationDecoder;
+use image::{DynamicImage, Frame, GenericImageView};
+
+#[cfg(feature = "gif")]
+use crate::AsciiError;
 
 pub trait FrameSource {
     fn dimensions(&self) -> (u32, u32);
     fn next_frame(&mut self) -> Option<DynamicImage>;
+
+    /// Display duration of the frame most recently returned by `next_frame`.
+    fn frame_delay(&self) -> Option<Duration> {
+        None
+    }
+
+    /// How often the source's frames should be played back.
+    fn loop_count(&self) -> LoopCount {
+        LoopCount::Infinite
+    }
+
+    /// Number of frames `next_frame` will still return, if known up front.
+    fn remaining_frames(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// Number of times an animation is played.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LoopCount {
+    #[default]
+    Infinite,
+    /// Play all frames this many times in total, then hold the last frame.
+    Finite(u16),
 }
 
+/// Frame source yielding a single still image.
 pub struct StaticFrame {
-    image: DynamicImage,
+    image: Option<DynamicImage>,
+    dimensions: (u32, u32),
 }
 
 impl StaticFrame {
     pub fn new(image: DynamicImage) -> Self {
-        Self { image }
+        Self { dimensions: image.dimensions(), image: Some(image) }
     }
 }
 
 impl FrameSource for StaticFrame {
     fn dimensions(&self) -> (u32, u32) {
-        self.image.dimensions()
+        self.dimensions
     }
 
     fn next_frame(&mut self) -> Option<DynamicImage> {
-        Some(self.image.clone())
+        self.image.take()
+    }
+
+    fn remaining_frames(&self) -> Option<usize> {
+        Some(self.image.is_some() as usize)
+    }
+}
+
+/// Frame source over decoded animation frames and their delays.
+pub struct AnimationFrames {
+    frames: VecDeque<Frame>,
+    dimensions: (u32, u32),
+    delay: Option<Duration>,
+    loop_count: LoopCount,
+}
+
+impl AnimationFrames {
+    pub fn new(frames: Vec<Frame>, loop_count: LoopCount) -> Self {
+        let dimensions = frames.first().map_or((0, 0), |frame| frame.buffer().dimensions());
+        Self { frames: frames.into(), dimensions, delay: None, loop_count }
+    }
+
+    /// Decode all frames of a GIF, along with its loop count.
+    #[cfg(feature = "gif")]
+    pub fn from_gif<R: Read>(mut reader: R) -> Result<Self, AsciiError> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data).map_err(image::ImageError::IoError)?;
+
+        // GIFs without a looping extension are played once.
+        let loop_count = match gif::DecodeOptions::new().read_info(Cursor::new(&data)) {
+            Ok(decoder) => match decoder.repeat() {
+                gif::Repeat::Infinite => LoopCount::Infinite,
+                gif::Repeat::Finite(repeat) => LoopCount::Finite(repeat.saturating_add(1)),
+            },
+            Err(_) => LoopCount::Infinite,
+        };
+
+        let decoder = image::codecs::gif::GifDecoder::new(Cursor::new(data))?;
+        let frames = decoder.into_frames().collect_frames()?;
+        Ok(Self::new(frames, loop_count))
+    }
+}
+
+impl AnimationFrames {
+    /// Shrink every stored frame that exceeds `max_width`x`max_height` to fit, keeping its
+    /// aspect ratio.
+    ///
+    /// Glyph grids are far coarser than large source frames, so a cap such as twice the target
+    /// grid's columns and rows saves memory and re-rendering work without changing the output.
+    pub fn with_max_dimensions(mut self, max_width: u32, max_height: u32) -> Self {
+        let (width, height) = self.dimensions;
+        if width <= max_width && height <= max_height {
+            return self;
+        }
+
+        let factor = (max_width as f32 / width as f32).min(max_height as f32 / height as f32);
+        let scale = |size: u32| ((size as f32 * factor).round() as u32).max(1);
+
+        let (target_width, target_height) = (scale(width), scale(height));
+        self.frames = self
+            .frames
+            .into_iter()
+            .map(|frame| {
+                let (left, top, delay) = (frame.left(), frame.top(), frame.delay());
+                let buffer = image::imageops::resize(
+                    frame.buffer(),
+                    target_width,
+                    target_height,
+                    image::imageops::FilterType::Triangle,
+                );
+                Frame::from_parts(buffer, scale(left), scale(top), delay)
+            })
+            .collect();
+        self.dimensions = (target_width, target_height);
+        self
+    }
+}
+
+impl FrameSource for AnimationFrames {
+    fn dimensions(&self) -> (u32, u32) {
+        self.dimensions
+    }
+
+    fn next_frame(&mut self) -> Option<DynamicImage> {
+        let frame = self.frames.pop_front()?;
+        self.delay = Some(frame.delay().into());
+        Some(DynamicImage::ImageRgba8(frame.into_buffer()))
+    }
+
+    fn frame_delay(&self) -> Option<Duration> {
+        self.delay
+    }
+
+    fn loop_count(&self) -> LoopCount {
+        self.loop_count
+    }
+
+    fn remaining_frames(&self) -> Option<usize> {
+        Some(self.frames.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "gif")]
+    use gif::{Encoder, Repeat};
+    use image::{Delay, RgbaImage};
+
+    use super::*;
+
+    #[test]
+    fn oversized_frames_are_capped() {
+        let frame = |width, height| {
+            Frame::from_parts(
+                RgbaImage::new(width, height),
+                0,
+                0,
+                Delay::from_numer_denom_ms(40, 1),
+            )
+        };
+
+        let frames = vec![frame(400, 200), frame(400, 200)];
+        let mut capped =
+            AnimationFrames::new(frames, LoopCount::Infinite).with_max_dimensions(100, 100);
+        assert_eq!((100, 50), capped.dimensions());
+        for _ in 0..2 {
+            assert_eq!(Some((100, 50)), capped.next_frame().map(|frame| frame.dimensions()));
+            assert_eq!(Some(Duration::from_millis(40)), capped.frame_delay());
+        }
+
+        let small = AnimationFrames::new(vec![frame(40, 20)], LoopCount::Infinite);
+        let mut small = small.with_max_dimensions(100, 100);
+        assert_eq!(Some((40, 20)), small.next_frame().map(|frame| frame.dimensions()));
+    }
+
+    #[cfg(feature = "gif")]
+    fn encode_gif(repeat: Option<Repeat>) -> Vec<u8> {
+        let mut data = Vec::new();
+        {
+            let mut encoder = Encoder::new(&mut data, 2, 2, &[0, 0, 0, 255, 255, 255]).unwrap();
+            if let Some(repeat) = repeat {
+                encoder.set_repeat(repeat).unwrap();
+            }
+            for (index, delay) in [(0, 5), (1, 10)] {
+                let mut frame = gif::Frame::from_indexed_pixels(2, 2, vec![index; 4], None);
+                frame.delay = delay;
+                encoder.write_frame(&frame).unwrap();
+            }
+        }
+        data
+    }
+
+    #[cfg(feature = "gif")]
+    #[test]
+    fn gif_loop_count() {
+        let infinite = AnimationFrames::from_gif(&encode_gif(Some(Repeat::Infinite))[..]).unwrap();
+        assert_eq!(LoopCount::Infinite, infinite.loop_count());
+
+        let twice = AnimationFrames::from_gif(&encode_gif(Some(Repeat::Finite(1)))[..]).unwrap();
+        assert_eq!(LoopCount::Finite(2), twice.loop_count());
+
+        let once = AnimationFrames::from_gif(&encode_gif(None)[..]).unwrap();
+        assert_eq!(LoopCount::Finite(1), once.loop_count());
+    }
+
+    #[cfg(feature = "gif")]
+    #[test]
+    fn gif_frame_delays() {
+        let mut frames = AnimationFrames::from_gif(&encode_gif(None)[..]).unwrap();
+        assert_eq!((2, 2), frames.dimensions());
+
+        let mut delays = Vec::new();
+        while frames.next_frame().is_some() {
+            delays.push(frames.frame_delay().unwrap());
+        }
+        assert_eq!(vec![Duration::from_millis(50), Duration::from_millis(100)], delays);
     }
 }
@@ -1,4 +1,7 @@
 pub mod adjust;
+pub mod color;
+pub mod decoder;
 pub mod edges;
 pub mod loader;
+pub mod orientation;
 pub mod resize;
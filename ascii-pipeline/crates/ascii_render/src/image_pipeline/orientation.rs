@@ -0,0 +1,103 @@
+use std::io::{BufRead, Seek};
+
+use image::DynamicImage;
+
+/// Image orientation as stored in the EXIF `Orientation` tag.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Orientation {
+    Normal,
+    FlipHorizontal,
+    Rotate180,
+    FlipVertical,
+    Transpose,
+    Rotate90,
+    Transverse,
+    Rotate270,
+}
+
+impl Orientation {
+    /// Map an EXIF orientation value (1-8) to its orientation.
+    pub fn from_exif(value: u32) -> Option<Self> {
+        let orientation = match value {
+            1 => Orientation::Normal,
+            2 => Orientation::FlipHorizontal,
+            3 => Orientation::Rotate180,
+            4 => Orientation::FlipVertical,
+            5 => Orientation::Transpose,
+            6 => Orientation::Rotate90,
+            7 => Orientation::Transverse,
+            8 => Orientation::Rotate270,
+            _ => return None,
+        };
+        Some(orientation)
+    }
+
+    /// Read the orientation from the EXIF metadata of an encoded image.
+    pub fn read<R: BufRead + Seek>(reader: &mut R) -> Option<Self> {
+        let exif = exif::Reader::new().read_from_container(reader).ok()?;
+        let field = exif.get_field(exif::Tag::Orientation, exif::In::PRIMARY)?;
+        Self::from_exif(field.value.get_uint(0)?)
+    }
+
+    /// Transform `image` so it is displayed upright.
+    pub fn apply(self, image: DynamicImage) -> DynamicImage {
+        match self {
+            Orientation::Normal => image,
+            Orientation::FlipHorizontal => image.fliph(),
+            Orientation::Rotate180 => image.rotate180(),
+            Orientation::FlipVertical => image.flipv(),
+            Orientation::Transpose => image.rotate90().fliph(),
+            Orientation::Rotate90 => image.rotate90(),
+            Orientation::Transverse => image.rotate270().fliph(),
+            Orientation::Rotate270 => image.rotate270(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{GenericImageView, GrayImage, Luma};
+
+    use super::*;
+
+    /// Maps an upright pixel back to the source pixel expected there.
+    type SourcePoint = fn(u32, u32) -> (u32, u32);
+
+    #[test]
+    fn exif_values_map_to_transforms() {
+        const W: u32 = 3;
+        const H: u32 = 2;
+
+        // Source with a unique value per pixel.
+        let source = GrayImage::from_fn(W, H, |x, y| Luma([(y * W + x) as u8]));
+
+        let cases: [(u32, SourcePoint); 8] = [
+            (1, |x, y| (x, y)),
+            (2, |x, y| (W - 1 - x, y)),
+            (3, |x, y| (W - 1 - x, H - 1 - y)),
+            (4, |x, y| (x, H - 1 - y)),
+            (5, |x, y| (y, x)),
+            (6, |x, y| (y, H - 1 - x)),
+            (7, |x, y| (W - 1 - y, H - 1 - x)),
+            (8, |x, y| (W - 1 - y, x)),
+        ];
+
+        for (value, source_point) in cases {
+            let orientation = Orientation::from_exif(value).unwrap();
+            let upright = orientation.apply(DynamicImage::ImageLuma8(source.clone()));
+            let expected_dimensions = if value >= 5 { (H, W) } else { (W, H) };
+            assert_eq!(expected_dimensions, upright.dimensions(), "orientation {value}");
+
+            for (x, y, pixel) in upright.to_luma8().enumerate_pixels() {
+                let (sx, sy) = source_point(x, y);
+                assert_eq!(source.get_pixel(sx, sy), pixel, "orientation {value} at ({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_exif_values() {
+        assert_eq!(None, Orientation::from_exif(0));
+        assert_eq!(None, Orientation::from_exif(9));
+    }
+}
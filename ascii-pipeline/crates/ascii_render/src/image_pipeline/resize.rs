@@ -1,15 +1,39 @@
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TargetGeometry {
     pub columns: u16,
     pub rows: u16,
     pub cell_aspect: f32,
 }
 
+/// Reason a layout can't be derived for a source image.
+#[derive(Clone, Copy, Debug, PartialEq, thiserror::Error)]
+pub enum LayoutError {
+    #[error("source image has zero width")]
+    ZeroSourceWidth,
+    #[error("source image has zero height")]
+    ZeroSourceHeight,
+    #[error("cell aspect ratio {0} is not a positive number")]
+    InvalidAspect(f32),
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum LayoutPolicy {
     FixedColumns(u16),
-    FitViewport { columns: u16, rows: u16, cell_aspect: f32 },
-    ScaleToHeight { rows: u16, cell_aspect: f32 },
+    FitViewport {
+        columns: u16,
+        rows: u16,
+        cell_aspect: f32,
+    },
+    /// Exactly `rows` rows, with as many columns as the image's aspect ratio needs.
+    ///
+    /// Columns are rounded to the nearest whole cell and never drop below one. When
+    /// `max_columns` caps a very wide image, rows shrink to match so it isn't squashed.
+    ScaleToHeight {
+        rows: u16,
+        cell_aspect: f32,
+        max_columns: Option<u16>,
+    },
 }
 
 impl LayoutPolicy {
@@ -18,9 +42,21 @@ impl LayoutPolicy {
         source_width: u32,
         source_height: u32,
         default_aspect: f32,
-    ) -> Option<TargetGeometry> {
-        if source_width == 0 || source_height == 0 {
-            return None;
+    ) -> Result<TargetGeometry, LayoutError> {
+        if source_width == 0 {
+            return Err(LayoutError::ZeroSourceWidth);
+        }
+        if source_height == 0 {
+            return Err(LayoutError::ZeroSourceHeight);
+        }
+
+        let aspect = match *self {
+            LayoutPolicy::FixedColumns(_) => default_aspect,
+            LayoutPolicy::FitViewport { cell_aspect, .. }
+            | LayoutPolicy::ScaleToHeight { cell_aspect, .. } => cell_aspect,
+        };
+        if !aspect.is_finite() || aspect <= 0. {
+            return Err(LayoutError::InvalidAspect(aspect));
         }
 
         let image_ratio = source_height as f32 / source_width as f32;
@@ -29,7 +65,7 @@ impl LayoutPolicy {
             LayoutPolicy::FixedColumns(columns) => {
                 let columns = columns.max(1);
                 let rows = ((image_ratio * columns as f32 * default_aspect).round() as u16).max(1);
-                Some(TargetGeometry { columns, rows, cell_aspect: default_aspect })
+                Ok(TargetGeometry { columns, rows, cell_aspect: default_aspect })
             },
             LayoutPolicy::FitViewport { columns, rows, cell_aspect } => {
                 let mut columns = columns.max(1);
@@ -45,13 +81,57 @@ impl LayoutPolicy {
                     rows_limit = rows;
                 }
 
-                Some(TargetGeometry { columns, rows: rows_limit, cell_aspect })
+                Ok(TargetGeometry { columns, rows: rows_limit, cell_aspect })
             },
-            LayoutPolicy::ScaleToHeight { rows, cell_aspect } => {
-                let rows = rows.max(1);
-                let columns = ((rows as f32) / (image_ratio * cell_aspect)).round() as u16;
-                Some(TargetGeometry { columns: columns.max(1), rows, cell_aspect })
+            LayoutPolicy::ScaleToHeight { rows, cell_aspect, max_columns } => {
+                let mut rows = rows.max(1);
+                let mut columns =
+                    (((rows as f32) / (image_ratio * cell_aspect)).round() as u16).max(1);
+
+                let max_columns = max_columns.map_or(u16::MAX, |max| max.max(1));
+                if columns > max_columns {
+                    columns = max_columns;
+                    rows = ((image_ratio * columns as f32 * cell_aspect).round() as u16).max(1);
+                }
+
+                Ok(TargetGeometry { columns, rows, cell_aspect })
             },
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derive_failures() {
+        let columns = LayoutPolicy::FixedColumns(80);
+        assert_eq!(Err(LayoutError::ZeroSourceWidth), columns.derive(0, 10, 0.5));
+        assert_eq!(Err(LayoutError::ZeroSourceHeight), columns.derive(10, 0, 0.5));
+        assert_eq!(Err(LayoutError::InvalidAspect(0.)), columns.derive(10, 10, 0.));
+
+        let height =
+            LayoutPolicy::ScaleToHeight { rows: 20, cell_aspect: f32::NAN, max_columns: None };
+        assert!(matches!(height.derive(10, 10, 0.5), Err(LayoutError::InvalidAspect(_))));
+    }
+
+    #[test]
+    fn scale_to_height_extreme_aspects() {
+        let policy = LayoutPolicy::ScaleToHeight { rows: 40, cell_aspect: 0.5, max_columns: None };
+        let geometry = |width, height| policy.derive(width, height, 0.5).unwrap();
+
+        assert_eq!((800, 40), (geometry(1000, 100).columns, geometry(1000, 100).rows));
+        assert_eq!((8, 40), (geometry(100, 1000).columns, geometry(100, 1000).rows));
+
+        let policy = LayoutPolicy::ScaleToHeight { rows: 4, cell_aspect: 0.5, max_columns: None };
+        assert_eq!(1, policy.derive(100, 1000, 0.5).unwrap().columns);
+
+        let capped =
+            LayoutPolicy::ScaleToHeight { rows: 40, cell_aspect: 0.5, max_columns: Some(200) };
+        let wide = capped.derive(1000, 100, 0.5).unwrap();
+        assert_eq!((200, 10), (wide.columns, wide.rows));
+        let tall = capped.derive(100, 1000, 0.5).unwrap();
+        assert_eq!((8, 40), (tall.columns, tall.rows));
+    }
+}
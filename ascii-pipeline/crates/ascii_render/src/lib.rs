@@ -1,29 +1,94 @@
 mod ascii;
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod bench;
 mod image_pipeline;
+mod raster;
 
+use std::io::Cursor;
+#[cfg(not(target_arch = "wasm32"))]
 use std::path::Path;
 
 use image::{DynamicImage, GenericImageView};
+use log::warn;
 
-pub use ascii::{
-    gradient::Gradient,
-    grid::{CellGlyph, GlyphGrid},
-    mapping::GlyphMapper,
+pub use ascii::ansi::{nearest_ansi_16, nearest_ansi_256, ANSI_16};
+pub use ascii::gradient::{Gradient, GradientError};
+pub use ascii::grid::{CellGlyph, GlyphGrid};
+pub use ascii::html::{to_html, HtmlOptions};
+pub use ascii::mapping::{Dither, GlyphMapper, OrientationFill};
+pub use ascii::series::{
+    Drift, FadeIn, GlyphFrame, GlyphGridSeries, PlayMode, Playback, Player, PlayerControl,
+    StartFrame, DEFAULT_FRAME_DELAY, MIN_FRAME_INTERVAL,
 };
-pub use image_pipeline::{
-    edges::{EdgeMode, EdgeSample},
-    loader::FrameSource,
-    resize::LayoutPolicy,
+pub use ascii::svg::to_svg;
+pub use image_pipeline::adjust::{AdaptiveThreshold, AutoLevels, Bilateral, Companding, ToneMap};
+pub use image_pipeline::color::{nearest_color, CGA, GAME_BOY};
+pub use image_pipeline::decoder::{Decoder, ImageCrateDecoder};
+pub use image_pipeline::edges::{
+    canny, convolve_edges, dilate, overlay, sobel_map, sobel_with_angle, BorderMode,
+    EdgeColorSource, EdgeKernel, EdgeMode, EdgeSample, Kernel,
 };
+pub use image_pipeline::loader::{AnimationFrames, FrameSource, LoopCount, StaticFrame};
+pub use image_pipeline::orientation::Orientation;
+pub use image_pipeline::resize::{LayoutError, LayoutPolicy, TargetGeometry};
+pub use raster::kitty::{encode_kitty, kitty_chunks, KITTY_CHUNK_SIZE};
+pub use raster::sixel::encode_sixel;
+pub use raster::{render_grid_to_image, render_to_image, FontConfig};
 
-use image_pipeline::{adjust, edges, resize::TargetGeometry};
+use ascii::mapping;
+use image_pipeline::{adjust, color, edges};
 
 #[derive(Debug, thiserror::Error)]
 pub enum AsciiError {
     #[error("failed to load image: {0}")]
     Image(#[from] image::ImageError),
-    #[error("unsupported layout dimensions")]
-    InvalidLayout,
+    #[error("unsupported layout dimensions: {0}")]
+    InvalidLayout(#[from] LayoutError),
+    #[error("frame dimensions do not match")]
+    GeometryMismatch,
+    #[error("grid rows have differing widths")]
+    RaggedRows,
+    #[error("grid of {0}x{1} cells exceeds the maximum of 65535 cells per side")]
+    GridTooLarge(u32, u32),
+    #[error("animation contains no renderable frames")]
+    NoFrames,
+    #[cfg(feature = "tokio")]
+    #[error("rendering task failed: {0}")]
+    Task(#[from] tokio::task::JoinError),
+}
+
+/// Alpha below which a cell counts as transparent.
+pub(crate) const TRANSPARENT_ALPHA: f32 = 0.1;
+
+/// Range font aspect ratios are clamped to before deriving a layout.
+pub const FONT_ASPECT_RANGE: (f32, f32) = (0.1, 4.0);
+
+/// Font aspect ratio used when the configured one is not a number.
+const DEFAULT_FONT_ASPECT: f32 = 0.55;
+
+/// How source pixels are turned into cells.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RenderMode {
+    /// Pick a gradient glyph per cell from its luminance.
+    #[default]
+    Glyphs,
+    /// Draw two pixels per cell with an upper half block, using the foreground color for the top
+    /// pixel and the background color for the bottom one.
+    HalfBlock,
+    /// Draw 2x4 pixels per cell as the dots of a Braille glyph, raised where the luminance reaches
+    /// [`AsciiOptions::braille_threshold`].
+    Braille,
+}
+
+/// Source of the glyph foreground colors.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorMode {
+    /// Gray levels matching the intensity each glyph was picked from.
+    Luminance,
+    /// The source pixel's color and alpha.
+    #[default]
+    ColorAlpha,
 }
 
 #[derive(Clone, Debug)]
@@ -34,57 +99,310 @@ pub struct AsciiOptions {
     pub brightness: f32,
     /// Contrast offset in the range [-255.0, 255.0].
     pub contrast: f32,
-    /// Font aspect ratio (height / width) assumed when deriving grid size.
+    /// Stretch the luminance between two percentiles to the full range before contrast and
+    /// brightness, so washed out images use the whole gradient.
+    pub auto_levels: Option<AutoLevels>,
+    /// Gamma applied to the luminance after contrast and brightness; above 1 lifts the midtones.
+    /// Values of zero or less are treated as 1.
+    pub gamma: f32,
+    /// Red, green and blue offsets in the range [-255.0, 255.0], for correcting color casts.
+    pub channel_brightness: [f32; 3],
+    /// Curve compressing luminance above 1 from high dynamic range inputs.
+    pub tone_map: ToneMap,
+    /// Logarithmic or exponential curve emphasizing shadows or highlights of the luminance.
+    pub companding: Companding,
+    /// Source pixel rectangle `(x, y, width, height)` to render instead of the whole image.
+    pub roi: Option<(u32, u32, u32, u32)>,
+    /// Grayscale mask stretched over the image (or its region of interest); each cell's alpha is
+    /// multiplied by the mask's luminance and cells it hides become blank.
+    pub mask: Option<DynamicImage>,
+    /// Radius of the median filter applied to the luminance, 0 to disable.
+    pub median_radius: u8,
+    /// Edge-preserving bilateral smoothing of the luminance, reducing banding in flat areas.
+    pub bilateral: Option<Bilateral>,
+    /// Standard deviation in cells of the Gaussian blur applied to the luminance to calm noisy
+    /// images, 0 to disable.
+    pub blur_sigma: f32,
+    /// Binarize the luminance against its local mean, for line art and documents.
+    pub adaptive_threshold: Option<AdaptiveThreshold>,
+    /// Font aspect ratio (height / width) assumed when deriving grid size, clamped to
+    /// [`FONT_ASPECT_RANGE`].
     pub font_aspect: f32,
+    /// Assume square cells, ignoring `font_aspect`, for media like image or SVG export.
+    pub square_cells: bool,
     /// Edge extraction mode.
     pub edge_mode: EdgeMode,
+    /// Glyph and shading of edge and non-edge cells when mapping edge orientations.
+    pub orientation_fill: OrientationFill,
+    /// Dithering between neighboring glyphs of the gradient.
+    pub dither: Dither,
+    /// Return the Sobel edge sample of every cell in [`RenderOutput::edge_samples`], for callers
+    /// post-processing by edge strength or direction. Not available for half blocks or Braille.
+    pub edge_samples: bool,
+    /// Return the luminance each glyph was picked from in [`RenderOutput::intensities`], for
+    /// callers analyzing the image as numbers. Not available for half blocks or Braille.
+    pub keep_intensity: bool,
+    /// Cell rendering strategy.
+    pub render_mode: RenderMode,
+    /// Luminance in `[0, 1]` at which Braille dots are raised.
+    pub braille_threshold: f32,
+    /// Source of the glyph colors in glyph mode; half blocks and Braille glyphs always use the
+    /// source colors.
+    pub color_mode: ColorMode,
+    /// Color temperature shift, warming the glyph colors when positive and cooling them when
+    /// negative.
+    pub temperature: f32,
+    /// Color each glyph with the average of its whole block of source pixels instead of the
+    /// resampled pixel, so thin colored features survive downscaling. Glyphs are still picked from
+    /// the resampled luminance.
+    pub area_color: bool,
+    /// Palette the glyph colors are snapped to.
+    pub palette: Option<Vec<[u8; 3]>>,
+    /// Rotate or flip images according to their EXIF orientation tag.
+    ///
+    /// Only images loaded from an encoded source, like [`AsciiRenderer::render_path`], carry
+    /// this metadata.
+    pub auto_orient: bool,
+    /// Glyph drawn in cells whose source pixels are transparent, instead of the mapped glyph.
+    pub transparent_char: Option<char>,
+}
+
+impl AsciiOptions {
+    /// Font aspect ratio the layout is derived with.
+    pub fn effective_font_aspect(&self) -> f32 {
+        let (min, max) = FONT_ASPECT_RANGE;
+        if self.square_cells {
+            1.0
+        } else if self.font_aspect.is_nan() {
+            DEFAULT_FONT_ASPECT
+        } else {
+            self.font_aspect.clamp(min, max)
+        }
+    }
 }
 
 impl Default for AsciiOptions {
     fn default() -> Self {
         Self {
-            gradient: Gradient::detailed(),
+            gradient: Gradient::default(),
             invert: false,
             brightness: 0.0,
             contrast: 0.0,
-            font_aspect: 0.55,
+            auto_levels: None,
+            gamma: 1.0,
+            channel_brightness: [0.0; 3],
+            tone_map: ToneMap::None,
+            companding: Companding::None,
+            roi: None,
+            mask: None,
+            median_radius: 0,
+            bilateral: None,
+            blur_sigma: 0.0,
+            adaptive_threshold: None,
+            font_aspect: DEFAULT_FONT_ASPECT,
+            square_cells: false,
             edge_mode: EdgeMode::None,
+            orientation_fill: OrientationFill::default(),
+            dither: Dither::None,
+            edge_samples: false,
+            keep_intensity: false,
+            render_mode: RenderMode::Glyphs,
+            braille_threshold: 0.5,
+            color_mode: ColorMode::ColorAlpha,
+            temperature: 0.0,
+            area_color: false,
+            palette: None,
+            auto_orient: true,
+            transparent_char: None,
         }
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RenderOutput {
     pub grid: GlyphGrid,
     pub geometry: TargetGeometry,
     /// Font aspect ratio used to derive the layout.
     pub assumed_font_aspect: f32,
+    /// Per-cell edge strength and direction, when requested with
+    /// [`AsciiOptions::edge_samples`].
+    pub edge_samples: Option<Vec<EdgeSample>>,
+    /// Per-cell luminance in `[0, 1]`, when requested with [`AsciiOptions::keep_intensity`].
+    pub intensities: Option<Vec<f32>>,
+}
+
+impl RenderOutput {
+    /// Format [`RenderOutput::intensities`] as one line of comma-separated values per row.
+    pub fn intensities_to_csv(&self) -> Option<String> {
+        let intensities = self.intensities.as_ref()?;
+        let rows: Vec<String> = intensities
+            .chunks(self.grid.width.max(1) as usize)
+            .map(|row| row.iter().map(f32::to_string).collect::<Vec<_>>().join(","))
+            .collect();
+        Some(rows.join("\n"))
+    }
 }
 
-#[derive(Default)]
+#[derive(Clone, Copy, Default)]
 pub struct AsciiRenderer;
 
 impl AsciiRenderer {
+    #[cfg(not(target_arch = "wasm32"))]
     pub fn render_path<P: AsRef<Path>>(
         &self,
         path: P,
         layout: LayoutPolicy,
         options: AsciiOptions,
     ) -> Result<RenderOutput, AsciiError> {
-        let image = image::open(path)?;
+        self.render_path_with(&ImageCrateDecoder, path, layout, options)
+    }
+
+    /// Read the file at `path` and render it through a custom `decoder`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn render_path_with<D: Decoder, P: AsRef<Path>>(
+        &self,
+        decoder: &D,
+        path: P,
+        layout: LayoutPolicy,
+        options: AsciiOptions,
+    ) -> Result<RenderOutput, AsciiError> {
+        let bytes = std::fs::read(path).map_err(image::ImageError::IoError)?;
+        self.render_bytes_with(decoder, &bytes, layout, options)
+    }
+
+    /// Decode and render an encoded image held in memory, without touching the filesystem.
+    pub fn render_bytes(
+        &self,
+        bytes: &[u8],
+        layout: LayoutPolicy,
+        options: AsciiOptions,
+    ) -> Result<RenderOutput, AsciiError> {
+        self.render_bytes_with(&ImageCrateDecoder, bytes, layout, options)
+    }
+
+    /// Decode `bytes` with a custom `decoder`, then render them like [`Self::render_bytes`].
+    pub fn render_bytes_with<D: Decoder>(
+        &self,
+        decoder: &D,
+        bytes: &[u8],
+        layout: LayoutPolicy,
+        options: AsciiOptions,
+    ) -> Result<RenderOutput, AsciiError> {
+        let mut image = decoder.decode(bytes)?;
+
+        if options.auto_orient {
+            if let Some(orientation) = Orientation::read(&mut Cursor::new(bytes)) {
+                image = orientation.apply(image);
+            }
+        }
+
         self.render_image(image, layout, options)
     }
 
+    /// Decode and render the image at `path` on tokio's blocking thread pool.
+    ///
+    /// This keeps decoding and rendering off the async executor's worker threads.
+    #[cfg(all(feature = "tokio", not(target_arch = "wasm32")))]
+    pub async fn render_path_async<P: AsRef<Path>>(
+        &self,
+        path: P,
+        layout: LayoutPolicy,
+        options: AsciiOptions,
+    ) -> Result<RenderOutput, AsciiError> {
+        let renderer = *self;
+        let path = path.as_ref().to_path_buf();
+        tokio::task::spawn_blocking(move || renderer.render_path(path, layout, options)).await?
+    }
+
     pub fn render_image(
         &self,
-        mut image: DynamicImage,
+        image: DynamicImage,
         layout: LayoutPolicy,
         options: AsciiOptions,
     ) -> Result<RenderOutput, AsciiError> {
+        let mask = options.mask.as_ref().map(|mask| match options.roi {
+            Some(roi) => crop_mask(mask, roi, image.dimensions()),
+            None => mask.clone(),
+        });
+
+        // `crop_imm` clamps rectangles reaching past the image to its bounds.
+        let image = match options.roi {
+            Some((x, y, width, height)) => image.crop_imm(x, y, width, height),
+            None => image,
+        };
 
         let (width, height) = image.dimensions();
-        let geometry =
-            layout.derive(width, height, options.font_aspect).ok_or(AsciiError::InvalidLayout)?;
+        let font_aspect = options.effective_font_aspect();
+        if !options.square_cells && font_aspect != options.font_aspect {
+            warn!("font aspect {} is out of range, using {}", options.font_aspect, font_aspect);
+        }
+        let geometry = layout.derive(width, height, font_aspect)?;
+
+        if options.render_mode == RenderMode::HalfBlock {
+            let mut pixels = image
+                .resize_exact(
+                    geometry.columns as u32,
+                    geometry.rows as u32 * 2,
+                    image::imageops::FilterType::CatmullRom,
+                )
+                .into_rgba8();
+            color::adjust_temperature(&mut pixels, options.temperature);
+            let [r, g, b] = options.channel_brightness;
+            color::apply_channel_brightness(&mut pixels, r, g, b);
+            if let Some(palette) = &options.palette {
+                color::quantize_to_palette(&mut pixels, palette);
+            }
+
+            let mut grid = mapping::map_half_blocks(&pixels, geometry.columns, geometry.rows);
+            if let Some(mask) = &mask {
+                apply_mask(&mut grid, mask, options.transparent_char);
+            }
+            return Ok(RenderOutput {
+                grid,
+                geometry,
+                assumed_font_aspect: font_aspect,
+                edge_samples: None,
+                intensities: None,
+            });
+        }
+
+        if options.render_mode == RenderMode::Braille {
+            let resized = image.resize_exact(
+                geometry.columns as u32 * 2,
+                geometry.rows as u32 * 4,
+                image::imageops::FilterType::CatmullRom,
+            );
+            let mut luminance = adjust::extract_luma(&resized, options.invert, options.tone_map);
+            adjust::apply_companding(&mut luminance, options.companding);
+            adjust::apply_contrast_and_brightness(
+                &mut luminance,
+                options.contrast,
+                options.brightness,
+            );
+
+            let mut pixels = resized.into_rgba8();
+            color::adjust_temperature(&mut pixels, options.temperature);
+            let [r, g, b] = options.channel_brightness;
+            color::apply_channel_brightness(&mut pixels, r, g, b);
+            if let Some(palette) = &options.palette {
+                color::quantize_to_palette(&mut pixels, palette);
+            }
+
+            let (columns, rows) = (geometry.columns, geometry.rows);
+            let threshold = options.braille_threshold;
+            let mut grid = mapping::map_braille(&pixels, &luminance, columns, rows, threshold);
+            if let Some(mask) = &mask {
+                apply_mask(&mut grid, mask, options.transparent_char);
+            }
+            return Ok(RenderOutput {
+                grid,
+                geometry,
+                assumed_font_aspect: font_aspect,
+                edge_samples: None,
+                intensities: None,
+            });
+        }
 
         let resized = image.resize_exact(
             geometry.columns as u32,
@@ -92,29 +410,785 @@ impl AsciiRenderer {
             image::imageops::FilterType::CatmullRom,
         );
 
-        let mut luminance = adjust::extract_luma(&resized, options.invert);
+        let mut luminance = adjust::extract_luma(&resized, options.invert, options.tone_map);
+        adjust::apply_companding(&mut luminance, options.companding);
+        adjust::median_filter(
+            &mut luminance,
+            geometry.columns,
+            geometry.rows,
+            options.median_radius,
+        );
+        if let Some(Bilateral { spatial_sigma, range_sigma }) = options.bilateral {
+            adjust::bilateral_filter(
+                &mut luminance,
+                geometry.columns,
+                geometry.rows,
+                spatial_sigma,
+                range_sigma,
+            );
+        }
+        adjust::gaussian_blur(&mut luminance, geometry.columns, geometry.rows, options.blur_sigma);
+        if let Some(AutoLevels { low, high }) = options.auto_levels {
+            adjust::auto_levels(&mut luminance, low, high);
+        }
         adjust::apply_contrast_and_brightness(&mut luminance, options.contrast, options.brightness);
+        adjust::apply_gamma(&mut luminance, options.gamma);
+        if let Some(AdaptiveThreshold { block, c }) = options.adaptive_threshold {
+            adjust::adaptive_threshold(&mut luminance, geometry.columns, geometry.rows, block, c);
+        }
+
+        let intensities = options.keep_intensity.then(|| luminance.clone());
+        let edge_samples = options.edge_samples.then(|| {
+            let (threshold, border, kernel) = match options.edge_mode {
+                EdgeMode::None => (0.0, BorderMode::default(), EdgeKernel::default()),
+                EdgeMode::SobelOverlay { threshold, .. } => {
+                    (threshold, BorderMode::Clamp, EdgeKernel::Sobel)
+                },
+                EdgeMode::Canny { low, .. } => (low, BorderMode::Clamp, EdgeKernel::Sobel),
+                EdgeMode::Sobel { threshold, border, kernel, .. }
+                | EdgeMode::SobelOriented { threshold, border, kernel } => {
+                    (threshold, border, kernel)
+                },
+            };
+            let (columns, rows) = (geometry.columns, geometry.rows);
+            edges::sobel_orientation(&luminance, columns, rows, kernel, threshold, border)
+        });
 
+        let mut edge_cells = None;
         let map = match options.edge_mode {
             EdgeMode::None => edges::EdgeResult::Intensity(luminance),
-            EdgeMode::Sobel { threshold } => {
-                let intensities =
-                    edges::sobel_map(&luminance, geometry.columns, geometry.rows, threshold);
+            EdgeMode::Sobel { threshold, border, dilate, kernel, .. } => {
+                let (columns, rows) = (geometry.columns, geometry.rows);
+                let (kernel_x, kernel_y) = kernel.kernels();
+                let mut intensities = edges::convolve_edges(
+                    &luminance, columns, rows, kernel_x, kernel_y, threshold, border,
+                );
+                edges::dilate(&mut intensities, columns, rows, dilate);
+                edge_cells = Some(intensities.iter().map(|&value| value > 0.).collect::<Vec<_>>());
+                edges::EdgeResult::Intensity(intensities)
+            },
+            EdgeMode::SobelOverlay { threshold, strength } => {
+                let (columns, rows) = (geometry.columns, geometry.rows);
+                let edges =
+                    edges::sobel_map(&luminance, columns, rows, threshold, BorderMode::Clamp);
+                let mut intensities = luminance;
+                edges::overlay(&mut intensities, &edges, strength);
                 edges::EdgeResult::Intensity(intensities)
             },
+            EdgeMode::Canny { low, high } => {
+                let (columns, rows) = (geometry.columns, geometry.rows);
+                edges::EdgeResult::Intensity(edges::canny(&luminance, columns, rows, low, high))
+            },
+            EdgeMode::SobelOriented { threshold, border, kernel } => {
+                let (columns, rows) = (geometry.columns, geometry.rows);
+                let samples = match &edge_samples {
+                    Some(samples) => samples.clone(),
+                    None => edges::sobel_orientation(
+                        &luminance, columns, rows, kernel, threshold, border,
+                    ),
+                };
+                edges::EdgeResult::Orientation(samples)
+            },
         };
 
-        let mut mapper = GlyphMapper::new(options.gradient.clone());
+        let mut mapper = GlyphMapper::new(options.gradient.clone()).with_dither(options.dither);
 
-        let grid = match map {
-            edges::EdgeResult::Intensity(mut intensities) => {
+        let mut grid = match map {
+            edges::EdgeResult::Intensity(intensities) => {
                 mapper.map_intensity(&intensities, geometry.columns, geometry.rows)
             },
             edges::EdgeResult::Orientation(samples) => {
-                mapper.map_orientation(&samples, geometry.columns, geometry.rows)
+                let (columns, rows) = (geometry.columns, geometry.rows);
+                mapper.map_orientation(&samples, columns, rows, options.orientation_fill)
             },
         };
 
-        Ok(RenderOutput { grid, geometry, assumed_font_aspect: options.font_aspect })
+        let (edge_glyph, edge_color) = match options.edge_mode {
+            EdgeMode::None
+            | EdgeMode::SobelOriented { .. }
+            | EdgeMode::SobelOverlay { .. }
+            | EdgeMode::Canny { .. } => (None, None),
+            EdgeMode::Sobel { edge_glyph, color, .. } => (edge_glyph, Some(color)),
+        };
+        if let (Some(ch), Some(active)) = (edge_glyph, &edge_cells) {
+            for (cell, &active) in grid.cells.iter_mut().zip(active) {
+                if active {
+                    cell.ch = ch;
+                }
+            }
+        }
+
+        let mut pixels = if options.area_color {
+            image.thumbnail_exact(geometry.columns as u32, geometry.rows as u32).into_rgba8()
+        } else {
+            resized.into_rgba8()
+        };
+        color::adjust_temperature(&mut pixels, options.temperature);
+        let [r, g, b] = options.channel_brightness;
+        color::apply_channel_brightness(&mut pixels, r, g, b);
+        if let Some(palette) = &options.palette {
+            color::quantize_to_palette(&mut pixels, palette);
+        }
+
+        // Colors that don't come from the quantized pixels are snapped to the palette one by one.
+        let snap = |color: [u8; 3]| match &options.palette {
+            Some(palette) => nearest_color(color, palette).map_or(color, |index| palette[index]),
+            None => color,
+        };
+        for (index, (cell, pixel)) in grid.cells.iter_mut().zip(pixels.pixels()).enumerate() {
+            let [r, g, b, a] = pixel.0;
+            let edge = edge_cells.as_ref().is_some_and(|active| active[index]);
+            // Mapping already set the gray of the intensity each glyph was picked from.
+            match edge_color.filter(|_| edge) {
+                Some(EdgeColorSource::Source) => cell.fg = [r, g, b],
+                Some(EdgeColorSource::Fixed(color)) => cell.fg = snap(color),
+                Some(EdgeColorSource::Magnitude) => cell.fg = snap(cell.fg),
+                None if options.color_mode == ColorMode::ColorAlpha => cell.fg = [r, g, b],
+                None => cell.fg = snap(cell.fg),
+            }
+            cell.alpha = a as f32 / 255.0;
+            if let Some(ch) = options.transparent_char.filter(|_| cell.is_transparent()) {
+                cell.ch = ch;
+            }
+        }
+        if let Some(mask) = &mask {
+            apply_mask(&mut grid, mask, options.transparent_char);
+        }
+
+        Ok(RenderOutput {
+            grid,
+            geometry,
+            assumed_font_aspect: font_aspect,
+            edge_samples,
+            intensities,
+        })
+    }
+
+    /// Render `image` as Braille glyphs, four times as detailed vertically and twice as detailed
+    /// horizontally as glyph mode, regardless of [`AsciiOptions::render_mode`].
+    pub fn render_braille(
+        &self,
+        image: DynamicImage,
+        layout: LayoutPolicy,
+        options: AsciiOptions,
+    ) -> Result<RenderOutput, AsciiError> {
+        self.render_image(image, layout, AsciiOptions {
+            render_mode: RenderMode::Braille,
+            ..options
+        })
+    }
+
+    /// Render every frame of `source` into a series.
+    ///
+    /// Frames without a valid layout are skipped; if none remain, this fails with
+    /// [`AsciiError::NoFrames`].
+    pub fn render_source<S: FrameSource>(
+        &self,
+        source: S,
+        layout: LayoutPolicy,
+        options: AsciiOptions,
+    ) -> Result<GlyphGridSeries, AsciiError> {
+        self.render_source_with_progress(source, layout, options, &|_, _| {})
+    }
+
+    /// Like [`AsciiRenderer::render_source`], calling `progress` with `(done, total)` after every
+    /// frame of `source`, including skipped ones.
+    ///
+    /// `total` is zero when the source can't tell how many frames it holds.
+    pub fn render_source_with_progress<S: FrameSource>(
+        &self,
+        mut source: S,
+        layout: LayoutPolicy,
+        options: AsciiOptions,
+        progress: &dyn Fn(usize, usize),
+    ) -> Result<GlyphGridSeries, AsciiError> {
+        let total = source.remaining_frames().unwrap_or(0);
+        let mut series =
+            GlyphGridSeries { loop_count: source.loop_count(), ..GlyphGridSeries::default() };
+        let mut done = 0;
+        while let Some(image) = source.next_frame() {
+            let delay = source.frame_delay().unwrap_or(DEFAULT_FRAME_DELAY);
+            let result = self.render_image(image, layout, options.clone());
+            done += 1;
+            progress(done, total);
+            let output = match result {
+                Ok(output) => output,
+                // Skip degenerate frames, like empty images, instead of failing the animation.
+                Err(AsciiError::InvalidLayout(_)) => continue,
+                Err(err) => return Err(err),
+            };
+            series.geometry.get_or_insert(output.geometry);
+            series.push(output.grid, delay);
+        }
+
+        if series.is_empty() {
+            return Err(AsciiError::NoFrames);
+        }
+        Ok(series)
+    }
+
+    /// Lazily render the frames of `source`, one per call to `next`.
+    ///
+    /// Unlike [`AsciiRenderer::render_source`], nothing is rendered until the iterator is
+    /// polled, so callers can consume frames as they are produced. Frames without a valid layout
+    /// are skipped the same way, and if none remain, the iterator yields a single
+    /// [`AsciiError::NoFrames`].
+    pub fn render_frames<'a, S: FrameSource + 'a>(
+        &'a self,
+        source: S,
+        layout: LayoutPolicy,
+        options: AsciiOptions,
+    ) -> impl Iterator<Item = Result<RenderOutput, AsciiError>> + 'a {
+        RenderFrames { renderer: self, source, layout, options, yielded: false }
+    }
+}
+
+/// Crop `mask` to the region of interest of a `source_size` image, scaled to the mask's size.
+fn crop_mask(
+    mask: &DynamicImage,
+    (x, y, width, height): (u32, u32, u32, u32),
+    (source_width, source_height): (u32, u32),
+) -> DynamicImage {
+    let (mask_width, mask_height) = mask.dimensions();
+    let scale_x = mask_width as f64 / source_width.max(1) as f64;
+    let scale_y = mask_height as f64 / source_height.max(1) as f64;
+    mask.crop_imm(
+        (x as f64 * scale_x).round() as u32,
+        (y as f64 * scale_y).round() as u32,
+        (width as f64 * scale_x).round() as u32,
+        (height as f64 * scale_y).round() as u32,
+    )
+}
+
+/// Multiply each cell's alpha by the mask's luminance, blanking the cells it hides.
+fn apply_mask(grid: &mut GlyphGrid, mask: &DynamicImage, transparent_char: Option<char>) {
+    let mask = mask
+        .resize_exact(grid.width as u32, grid.height as u32, image::imageops::FilterType::Triangle)
+        .to_luma32f();
+    for (cell, value) in grid.cells.iter_mut().zip(mask.pixels()) {
+        cell.alpha *= value.0[0].clamp(0.0, 1.0);
+        if value.0[0] < TRANSPARENT_ALPHA {
+            cell.ch = transparent_char.unwrap_or(' ');
+        }
+    }
+}
+
+struct RenderFrames<'a, S> {
+    renderer: &'a AsciiRenderer,
+    source: S,
+    layout: LayoutPolicy,
+    options: AsciiOptions,
+    /// Whether anything was yielded yet, to report a source without frames once.
+    yielded: bool,
+}
+
+impl<S: FrameSource> Iterator for RenderFrames<'_, S> {
+    type Item = Result<RenderOutput, AsciiError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let Some(image) = self.source.next_frame() else {
+                let empty = !self.yielded;
+                self.yielded = true;
+                return empty.then_some(Err(AsciiError::NoFrames));
+            };
+            match self.renderer.render_image(image, self.layout, self.options.clone()) {
+                // Skipped like in `render_source`, so both yield the same frames.
+                Err(AsciiError::InvalidLayout(_)) => continue,
+                result => {
+                    self.yielded = true;
+                    return Some(result);
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{GrayImage, Luma, Rgb, RgbImage, Rgba, RgbaImage};
+
+    use super::*;
+
+    struct Frames(Vec<DynamicImage>);
+
+    impl FrameSource for Frames {
+        fn dimensions(&self) -> (u32, u32) {
+            self.0.first().map_or((0, 0), |frame| frame.dimensions())
+        }
+
+        fn next_frame(&mut self) -> Option<DynamicImage> {
+            (!self.0.is_empty()).then(|| self.0.remove(0))
+        }
+
+        fn remaining_frames(&self) -> Option<usize> {
+            Some(self.0.len())
+        }
+    }
+
+    fn frames() -> Frames {
+        let frames = (0..3u8)
+            .map(|i| {
+                let image = GrayImage::from_fn(16, 8, |x, y| {
+                    Luma([(x * 16) as u8 ^ (y * 30) as u8 ^ (i * 80)])
+                });
+                DynamicImage::ImageLuma8(image)
+            })
+            .collect();
+        Frames(frames)
+    }
+
+    #[test]
+    fn render_frames_matches_render_source() {
+        let renderer = AsciiRenderer;
+        let layout = LayoutPolicy::FixedColumns(8);
+        // An empty frame in the middle has no valid layout and is skipped by both.
+        let frames = || {
+            let mut frames = frames();
+            frames.0.insert(1, DynamicImage::new_luma8(0, 0));
+            frames
+        };
+
+        let series = renderer.render_source(frames(), layout, AsciiOptions::default()).unwrap();
+        let streamed = renderer
+            .render_frames(frames(), layout, AsciiOptions::default())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(3, series.len());
+        assert_eq!(series.len(), streamed.len());
+        for (output, grid) in streamed.iter().zip(series.grids()) {
+            assert_eq!(grid, &output.grid);
+            assert_eq!(series.geometry, Some(output.geometry));
+        }
+    }
+
+    #[test]
+    fn render_frames_reports_no_frames() {
+        let empty = Frames(vec![DynamicImage::new_luma8(0, 0)]);
+        let mut frames = AsciiRenderer.render_frames(
+            empty,
+            LayoutPolicy::FixedColumns(8),
+            AsciiOptions::default(),
+        );
+
+        assert!(matches!(frames.next(), Some(Err(AsciiError::NoFrames))));
+        assert!(frames.next().is_none());
+    }
+
+    #[test]
+    fn palette_colors() {
+        let image = RgbImage::from_fn(16, 16, |x, y| Rgb([(x * 16) as u8, (y * 16) as u8, 128]));
+        for color_mode in [ColorMode::ColorAlpha, ColorMode::Luminance] {
+            let palette = Some(GAME_BOY.to_vec());
+            let options = AsciiOptions { palette, color_mode, ..AsciiOptions::default() };
+
+            let output = AsciiRenderer
+                .render_image(
+                    DynamicImage::ImageRgb8(image.clone()),
+                    LayoutPolicy::FixedColumns(8),
+                    options,
+                )
+                .unwrap();
+
+            assert!(output.grid.cells.iter().all(|cell| GAME_BOY.contains(&cell.fg)));
+        }
+    }
+
+    #[test]
+    fn half_block_cells() {
+        // Vertical gradient getting brighter towards the bottom.
+        let image = GrayImage::from_fn(2, 8, |_, y| Luma([(y * 32) as u8]));
+        let options =
+            AsciiOptions { render_mode: RenderMode::HalfBlock, ..AsciiOptions::default() };
+
+        let output = AsciiRenderer
+            .render_image(DynamicImage::ImageLuma8(image), LayoutPolicy::FixedColumns(2), options)
+            .unwrap();
+
+        assert_eq!((2, 4), (output.grid.width, output.grid.height));
+        let column: Vec<&CellGlyph> = output.grid.cells.iter().step_by(2).collect();
+        for cell in &column {
+            assert_eq!('▀', cell.ch);
+            assert!(cell.fg[0] < cell.bg.unwrap()[0], "{cell:?}");
+        }
+        for pair in column.windows(2) {
+            assert!(pair[0].bg.unwrap()[0] < pair[1].fg[0]);
+        }
+    }
+
+    #[test]
+    fn braille_cells() {
+        // Two cells of 2x4 pixels: a bright one, and a dark one with a transparent top half.
+        let image = RgbaImage::from_fn(4, 4, |x, y| match (x, y) {
+            (0..=1, _) => Rgba([255, 255, 255, 255]),
+            (_, 0..=1) => Rgba([255, 255, 255, 0]),
+            _ => Rgba([0, 0, 0, 255]),
+        });
+        let options = AsciiOptions { font_aspect: 0.5, ..AsciiOptions::default() };
+
+        let output = AsciiRenderer
+            .render_braille(DynamicImage::ImageRgba8(image), LayoutPolicy::FixedColumns(2), options)
+            .unwrap();
+
+        assert_eq!((2, 1), (output.grid.width, output.grid.height));
+        assert_eq!(vec!["⣿⠀"], output.grid.rows().collect::<Vec<_>>());
+        assert_eq!([255; 3], output.grid.cells[0].fg);
+    }
+
+    #[test]
+    fn transparent_cells_use_fill_char() {
+        let image = DynamicImage::ImageRgba8(RgbaImage::new(8, 8));
+        let layout = LayoutPolicy::FixedColumns(4);
+
+        let mapped = AsciiRenderer.render_image(image.clone(), layout, AsciiOptions::default());
+        assert!(mapped.unwrap().grid.cells.iter().all(|cell| cell.ch != '.'));
+
+        let options = AsciiOptions { transparent_char: Some('.'), ..AsciiOptions::default() };
+        let filled = AsciiRenderer.render_image(image, layout, options).unwrap();
+        assert!(filled.grid.cells.iter().all(|cell| cell.ch == '.' && cell.alpha == 0.));
+    }
+
+    #[test]
+    fn font_aspect_is_clamped() {
+        let image = DynamicImage::ImageLuma8(GrayImage::new(20, 20));
+        let layout = LayoutPolicy::FixedColumns(10);
+
+        for (font_aspect, expected) in [(0.0, 0.1), (-2.0, 0.1), (100.0, 4.0), (f32::NAN, 0.55)] {
+            let options = AsciiOptions { font_aspect, ..AsciiOptions::default() };
+            let output = AsciiRenderer.render_image(image.clone(), layout, options).unwrap();
+            assert_eq!(expected, output.assumed_font_aspect);
+            assert!(output.grid.height >= 1 && output.grid.height <= 40);
+        }
+    }
+
+    #[test]
+    fn channel_brightness_shifts_colors() {
+        let image = DynamicImage::ImageRgb8(RgbImage::from_pixel(8, 8, Rgb([100, 100, 100])));
+        let options = AsciiOptions { channel_brightness: [40., 0., 0.], ..AsciiOptions::default() };
+
+        let output =
+            AsciiRenderer.render_image(image, LayoutPolicy::FixedColumns(4), options).unwrap();
+
+        assert!(output.grid.cells.iter().all(|cell| cell.fg == [140, 100, 100]));
+    }
+
+    #[test]
+    fn square_cells_keep_proportions() {
+        let image = DynamicImage::ImageLuma8(GrayImage::new(20, 20));
+        let options = AsciiOptions { square_cells: true, ..AsciiOptions::default() };
+
+        let output =
+            AsciiRenderer.render_image(image, LayoutPolicy::FixedColumns(10), options).unwrap();
+
+        assert_eq!((10, 10), (output.grid.width, output.grid.height));
+        assert_eq!(1.0, output.assumed_font_aspect);
+    }
+
+    #[cfg(all(feature = "tokio", feature = "png"))]
+    #[tokio::test]
+    async fn render_path_async_matches_sync() {
+        let path =
+            std::env::temp_dir().join(format!("ascii_render_async_{}.png", std::process::id()));
+        let image = RgbImage::from_fn(16, 16, |x, y| Rgb([(x * 16) as u8, (y * 16) as u8, 0]));
+        image.save(&path).unwrap();
+        let layout = LayoutPolicy::FixedColumns(8);
+
+        let output = AsciiRenderer.render_path_async(&path, layout, AsciiOptions::default()).await;
+        let expected = AsciiRenderer.render_path(&path, layout, AsciiOptions::default());
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(expected.unwrap(), output.unwrap());
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn render_bytes_matches_render_path() {
+        let image = RgbImage::from_fn(16, 16, |x, y| Rgb([(x * 16) as u8, (y * 16) as u8, 0]));
+        let mut bytes = Vec::new();
+        image.write_to(&mut Cursor::new(&mut bytes), image::ImageOutputFormat::Png).unwrap();
+        let path =
+            std::env::temp_dir().join(format!("ascii_render_bytes_{}.png", std::process::id()));
+        std::fs::write(&path, &bytes).unwrap();
+        let layout = LayoutPolicy::FixedColumns(8);
+
+        let from_bytes = AsciiRenderer.render_bytes(&bytes, layout, AsciiOptions::default());
+        let from_path = AsciiRenderer.render_path(&path, layout, AsciiOptions::default());
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(from_path.unwrap(), from_bytes.unwrap());
+    }
+
+    #[test]
+    fn custom_decoder_feeds_pipeline() {
+        struct Solid;
+
+        impl Decoder for Solid {
+            fn decode(&self, _bytes: &[u8]) -> Result<DynamicImage, AsciiError> {
+                Ok(DynamicImage::ImageRgb8(RgbImage::from_pixel(8, 8, Rgb([200, 40, 40]))))
+            }
+        }
+
+        let layout = LayoutPolicy::FixedColumns(4);
+        let output =
+            AsciiRenderer.render_bytes_with(&Solid, b"DICM", layout, AsciiOptions::default());
+
+        let grid = output.unwrap().grid;
+        assert_eq!(4, grid.width);
+        assert_eq!(grid.width as usize * grid.height as usize, grid.cells.len());
+        assert!(grid
+            .cells
+            .iter()
+            .all(|cell| cell.fg == [200, 40, 40] && cell.ch == grid.cells[0].ch));
+    }
+
+    #[test]
+    fn render_source_reports_progress() {
+        let calls = std::cell::RefCell::new(Vec::new());
+        let layout = LayoutPolicy::FixedColumns(8);
+
+        let progress = |done, total| calls.borrow_mut().push((done, total));
+        AsciiRenderer
+            .render_source_with_progress(frames(), layout, AsciiOptions::default(), &progress)
+            .unwrap();
+
+        assert_eq!(vec![(1, 3), (2, 3), (3, 3)], calls.into_inner());
+    }
+
+    #[test]
+    fn invalid_frames_yield_no_frames() {
+        let layout = LayoutPolicy::FixedColumns(4);
+        let empty = Frames(vec![DynamicImage::ImageLuma8(GrayImage::new(0, 0)); 2]);
+
+        let result = AsciiRenderer.render_source(empty, layout, AsciiOptions::default());
+        assert!(matches!(result, Err(AsciiError::NoFrames)));
+
+        let result =
+            AsciiRenderer.render_source(Frames(Vec::new()), layout, AsciiOptions::default());
+        assert!(matches!(result, Err(AsciiError::NoFrames)));
+    }
+
+    #[test]
+    fn color_modes() {
+        let image = DynamicImage::ImageRgb8(RgbImage::from_pixel(8, 8, Rgb([200, 40, 40])));
+        let layout = LayoutPolicy::FixedColumns(4);
+
+        let options = AsciiOptions { color_mode: ColorMode::Luminance, ..AsciiOptions::default() };
+        let gray = AsciiRenderer.render_image(image.clone(), layout, options).unwrap();
+        let options = AsciiOptions { color_mode: ColorMode::ColorAlpha, ..AsciiOptions::default() };
+        let colored = AsciiRenderer.render_image(image, layout, options).unwrap();
+
+        assert_eq!(gray.grid.rows().collect::<Vec<_>>(), colored.grid.rows().collect::<Vec<_>>());
+        // Rec. 709 luma of the source color.
+        assert!(gray.grid.cells.iter().all(|cell| cell.fg == [74; 3]));
+        assert!(colored.grid.cells.iter().all(|cell| cell.fg == [200, 40, 40]));
+    }
+
+    #[test]
+    fn area_color_averages_source_block() {
+        let image =
+            RgbImage::from_fn(4, 4, |x, _| Rgb(if x % 2 == 0 { [255, 0, 0] } else { [0, 0, 255] }));
+        let layout = LayoutPolicy::FixedColumns(1);
+        let options =
+            AsciiOptions { area_color: true, square_cells: true, ..AsciiOptions::default() };
+
+        let output =
+            AsciiRenderer.render_image(DynamicImage::ImageRgb8(image), layout, options).unwrap();
+
+        let [r, g, b] = output.grid.cells[0].fg;
+        assert_eq!((1, 0), (output.grid.cells.len(), g));
+        assert!(r.abs_diff(128) <= 1 && b.abs_diff(128) <= 1, "{:?}", [r, g, b]);
+    }
+
+    #[test]
+    fn roi_crops_before_resize() {
+        let image =
+            RgbImage::from_fn(16, 8, |x, _| Rgb(if x < 8 { [255, 0, 0] } else { [0, 0, 255] }));
+        let image = DynamicImage::ImageRgb8(image);
+        let layout = LayoutPolicy::FixedColumns(4);
+
+        let options = AsciiOptions { roi: Some((0, 0, 8, 8)), ..AsciiOptions::default() };
+        let left = AsciiRenderer.render_image(image.clone(), layout, options).unwrap();
+        assert!(left.grid.cells.iter().all(|cell| cell.fg == [255, 0, 0]));
+
+        // Clamped to the right half.
+        let options = AsciiOptions { roi: Some((8, 0, 100, 100)), ..AsciiOptions::default() };
+        let right = AsciiRenderer.render_image(image, layout, options).unwrap();
+        assert!(right.grid.cells.iter().all(|cell| cell.fg == [0, 0, 255]));
+    }
+
+    #[test]
+    fn mask_blanks_hidden_cells() {
+        let image = DynamicImage::ImageLuma8(GrayImage::new(16, 16));
+        let mask = GrayImage::from_fn(8, 8, |x, _| if x < 4 { Luma([255]) } else { Luma([0]) });
+        let options = AsciiOptions {
+            mask: Some(DynamicImage::ImageLuma8(mask)),
+            font_aspect: 1.0,
+            ..AsciiOptions::default()
+        };
+
+        let output =
+            AsciiRenderer.render_image(image, LayoutPolicy::FixedColumns(8), options).unwrap();
+
+        for row in output.grid.to_2d() {
+            assert!(row[..4].iter().all(|cell| cell.ch != ' ' && cell.alpha == 1.0));
+            assert!(row[4..].iter().all(|cell| cell.ch == ' ' && cell.alpha == 0.0));
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn render_output_json_round_trip() {
+        let image = RgbImage::from_fn(16, 16, |x, y| Rgb([(x * 16) as u8, (y * 16) as u8, 128]));
+        let options =
+            AsciiOptions { edge_samples: true, keep_intensity: true, ..Default::default() };
+        let output = AsciiRenderer
+            .render_image(DynamicImage::ImageRgb8(image), LayoutPolicy::FixedColumns(8), options)
+            .unwrap();
+
+        let json = serde_json::to_string(&output).unwrap();
+        let parsed: RenderOutput = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(output.grid, parsed.grid);
+        assert_eq!(output, parsed);
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(8, value["grid"]["width"]);
+        assert!(value["grid"]["cells"][0]["ch"].is_string());
+    }
+
+    #[test]
+    fn edge_glyph_marks_active_edges() {
+        let image = GrayImage::from_fn(32, 32, |x, y| Luma(if x + y < 32 { [0] } else { [255] }));
+        let options = AsciiOptions {
+            gradient: Gradient::binary(),
+            edge_mode: EdgeMode::Sobel {
+                threshold: 0.2,
+                edge_glyph: Some('#'),
+                color: EdgeColorSource::Source,
+                border: BorderMode::Clamp,
+                dilate: 0,
+                kernel: EdgeKernel::Sobel,
+            },
+            edge_samples: true,
+            ..AsciiOptions::default()
+        };
+
+        let output = AsciiRenderer
+            .render_image(DynamicImage::ImageLuma8(image), LayoutPolicy::FixedColumns(16), options)
+            .unwrap();
+
+        let samples = output.edge_samples.unwrap();
+        assert!(samples.iter().any(|sample| sample.active));
+        for (cell, sample) in output.grid.cells.iter().zip(&samples) {
+            assert_eq!(sample.active, cell.ch == '#', "{cell:?} {sample:?}");
+        }
+    }
+
+    #[test]
+    fn oriented_edges() {
+        // Dark left half, so the only edge runs vertically down the middle.
+        let image = GrayImage::from_fn(32, 32, |x, _| Luma(if x < 16 { [0] } else { [255] }));
+        let options = AsciiOptions {
+            edge_mode: EdgeMode::SobelOriented {
+                threshold: 0.2,
+                border: BorderMode::Clamp,
+                kernel: EdgeKernel::Scharr,
+            },
+            square_cells: true,
+            ..AsciiOptions::default()
+        };
+
+        let output = AsciiRenderer
+            .render_image(DynamicImage::ImageLuma8(image), LayoutPolicy::FixedColumns(16), options)
+            .unwrap();
+
+        for row in output.grid.rows() {
+            assert_eq!("       ||       ", row);
+        }
+    }
+
+    #[test]
+    fn edge_colors() {
+        let image = RgbImage::from_fn(32, 32, |x, y| {
+            Rgb(if (8..24).contains(&x) && (8..24).contains(&y) { [255, 0, 0] } else { [0; 3] })
+        });
+        let image = DynamicImage::ImageRgb8(image);
+        let layout = LayoutPolicy::FixedColumns(32);
+        let render = |color| {
+            let edge_mode = EdgeMode::Sobel {
+                threshold: 0.1,
+                edge_glyph: Some('#'),
+                color,
+                border: BorderMode::Clamp,
+                dilate: 0,
+                kernel: EdgeKernel::Sobel,
+            };
+            let options = AsciiOptions {
+                edge_mode,
+                color_mode: ColorMode::Luminance,
+                square_cells: true,
+                ..AsciiOptions::default()
+            };
+            let grid = AsciiRenderer.render_image(image.clone(), layout, options).unwrap().grid;
+            let edges: Vec<[u8; 3]> =
+                grid.cells.iter().filter(|cell| cell.ch == '#').map(|cell| cell.fg).collect();
+            assert!(!edges.is_empty());
+            edges
+        };
+
+        let source = render(EdgeColorSource::Source);
+        assert!(source.contains(&[255, 0, 0]));
+        assert!(source.iter().all(|&[_, g, b]| g == 0 && b == 0));
+
+        let fixed = render(EdgeColorSource::Fixed([0, 255, 0]));
+        assert!(fixed.iter().all(|&fg| fg == [0, 255, 0]));
+
+        let magnitude = render(EdgeColorSource::Magnitude);
+        assert!(magnitude.iter().all(|&[r, g, b]| r == g && g == b && r > 0));
+    }
+
+    #[test]
+    fn intensities_as_csv() {
+        let image = GrayImage::from_fn(2, 2, |x, y| Luma([if x == y { 0 } else { 255 }]));
+        let layout = LayoutPolicy::FixedColumns(2);
+        let options =
+            AsciiOptions { keep_intensity: true, square_cells: true, ..AsciiOptions::default() };
+
+        let output =
+            AsciiRenderer.render_image(DynamicImage::ImageLuma8(image), layout, options).unwrap();
+
+        assert_eq!(Some(vec![0., 1., 1., 0.]), output.intensities);
+        assert_eq!(Some("0,1\n1,0".into()), output.intensities_to_csv());
+    }
+
+    #[test]
+    fn edge_samples_per_cell() {
+        let image = GrayImage::from_fn(32, 32, |x, y| Luma(if x + y < 32 { [0] } else { [255] }));
+        let options = AsciiOptions { edge_samples: true, ..AsciiOptions::default() };
+
+        let output = AsciiRenderer
+            .render_image(DynamicImage::ImageLuma8(image), LayoutPolicy::FixedColumns(16), options)
+            .unwrap();
+
+        let samples = output.edge_samples.unwrap();
+        assert_eq!(output.grid.cells.len(), samples.len());
+        assert!(samples.iter().any(|sample| sample.active));
+        assert!(samples.iter().all(|sample| (0.0..180.0).contains(&sample.angle_degrees)));
+
+        let output = AsciiRenderer.render_image(
+            DynamicImage::ImageLuma8(GrayImage::new(4, 4)),
+            LayoutPolicy::FixedColumns(4),
+            AsciiOptions::default(),
+        );
+        assert_eq!(None, output.unwrap().edge_samples);
+    }
+
+    #[test]
+    fn static_frame_yields_once() {
+        let image = DynamicImage::ImageLuma8(GrayImage::new(4, 4));
+        let layout = LayoutPolicy::FixedColumns(4);
+        let outputs =
+            AsciiRenderer.render_frames(StaticFrame::new(image), layout, AsciiOptions::default());
+        assert_eq!(1, outputs.count());
     }
 }
@@ -0,0 +1,92 @@
+//! Kitty graphics protocol encoding of rasterized grids.
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use image::RgbaImage;
+
+use super::render_grid_to_image;
+use crate::ascii::grid::GlyphGrid;
+
+/// Largest base64 payload the protocol accepts in a single escape sequence.
+pub const KITTY_CHUNK_SIZE: usize = 4096;
+
+/// Encode an image as kitty graphics protocol escapes that transmit and display it.
+///
+/// The raw RGBA data is base64 encoded and split across as many escapes as needed.
+pub fn encode_kitty(image: &RgbaImage) -> String {
+    let payload = STANDARD.encode(image.as_raw());
+    let chunks = kitty_chunks(&payload);
+
+    let mut kitty = String::new();
+    for (index, chunk) in chunks.iter().enumerate() {
+        let more = (index + 1 < chunks.len()) as u8;
+        if index == 0 {
+            let (width, height) = image.dimensions();
+            kitty.push_str(&format!("\x1b_Ga=T,f=32,s={},v={},m={};", width, height, more));
+        } else {
+            kitty.push_str(&format!("\x1b_Gm={};", more));
+        }
+        kitty.push_str(chunk);
+        kitty.push_str("\x1b\\");
+    }
+    kitty
+}
+
+/// Split a base64 payload into pieces of at most `KITTY_CHUNK_SIZE` bytes.
+///
+/// An empty payload still produces one empty chunk, so the image is always transmitted.
+pub fn kitty_chunks(payload: &str) -> Vec<&str> {
+    if payload.is_empty() {
+        return vec![payload];
+    }
+
+    // Base64 is pure ASCII, so byte offsets are always character boundaries.
+    (0..payload.len())
+        .step_by(KITTY_CHUNK_SIZE)
+        .map(|start| &payload[start..(start + KITTY_CHUNK_SIZE).min(payload.len())])
+        .collect()
+}
+
+impl GlyphGrid {
+    /// Rasterize the grid with `cell_width`x`cell_height` pixel cells for the kitty protocol.
+    pub fn to_kitty(&self, cell_width: u32, cell_height: u32) -> String {
+        encode_kitty(&render_grid_to_image(self, cell_width, cell_height, false))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+
+    use super::*;
+
+    #[test]
+    fn chunks_split_large_payloads() {
+        let payload = "A".repeat(2 * KITTY_CHUNK_SIZE + 344);
+
+        let chunks = kitty_chunks(&payload);
+
+        let lengths: Vec<usize> = chunks.iter().map(|chunk| chunk.len()).collect();
+        assert_eq!(vec![KITTY_CHUNK_SIZE, KITTY_CHUNK_SIZE, 344], lengths);
+        assert_eq!(payload, chunks.concat());
+        assert_eq!(vec![""], kitty_chunks(""));
+    }
+
+    #[test]
+    fn escapes_mark_continuation() {
+        // 40x40 RGBA is 6400 bytes, or 8536 bytes of base64.
+        let image = RgbaImage::from_pixel(40, 40, Rgba([255, 0, 0, 255]));
+
+        let kitty = encode_kitty(&image);
+
+        let escapes: Vec<&str> = kitty.split_terminator("\x1b\\").collect();
+        assert_eq!(3, escapes.len());
+        assert!(escapes[0].starts_with("\x1b_Ga=T,f=32,s=40,v=40,m=1;"));
+        assert!(escapes[1].starts_with("\x1b_Gm=1;"));
+        assert!(escapes[2].starts_with("\x1b_Gm=0;"));
+
+        let payload: String =
+            escapes.iter().map(|escape| escape.split_once(';').unwrap().1).collect();
+        assert_eq!(image.as_raw(), &STANDARD.decode(payload).unwrap());
+    }
+}
@@ -0,0 +1,186 @@
+//! Rasterization of glyph grids back into pixels.
+
+use ab_glyph::{point, Font, FontRef, PxScale, ScaleFont};
+use image::{Rgba, RgbaImage};
+
+use crate::ascii::grid::GlyphGrid;
+
+pub mod kitty;
+pub mod sixel;
+
+/// Monospace font bundled for rasterization.
+const FONT_DATA: &[u8] = include_bytes!("../../fonts/DejaVuSansMono.ttf");
+
+/// Coverage above which a glyph pixel is drawn.
+const COVERAGE_THRESHOLD: f32 = 0.5;
+
+/// Size and smoothing of the bundled font for [`render_to_image`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FontConfig {
+    /// Line height in pixels, from the font's ascent to its descent.
+    pub size: f32,
+    /// Blend glyph edges by coverage, see [`render_grid_to_image`].
+    pub antialias: bool,
+}
+
+impl Default for FontConfig {
+    fn default() -> Self {
+        Self { size: 16., antialias: true }
+    }
+}
+
+impl FontConfig {
+    /// Cell size in pixels: the font's advance width by its line height, so rendered grids keep
+    /// the proportions they have in a terminal using the same font.
+    pub fn cell_size(&self) -> (u32, u32) {
+        let font = FontRef::try_from_slice(FONT_DATA).expect("bundled font is valid");
+        let scaled = font.as_scaled(PxScale::from(self.size.max(1.)));
+        let advance = scaled.h_advance(font.glyph_id('M'));
+        (advance.round().max(1.) as u32, scaled.height().round().max(1.) as u32)
+    }
+}
+
+/// Draw `grid` with the bundled monospace font, in cells sized by [`FontConfig::cell_size`].
+pub fn render_to_image(grid: &GlyphGrid, font: &FontConfig) -> RgbaImage {
+    let (cell_width, cell_height) = font.cell_size();
+    render_grid_to_image(grid, cell_width, cell_height, font.antialias)
+}
+
+/// Draw every cell of `grid` into a `cell_width`x`cell_height` block of a transparent canvas.
+///
+/// Cells with a background color fill their block with it; glyphs are stamped in the cell's
+/// foreground color at the cell's alpha. With `antialias`, glyph edges are blended over the
+/// background by their coverage instead of being drawn only where coverage reaches one half.
+pub fn render_grid_to_image(
+    grid: &GlyphGrid,
+    cell_width: u32,
+    cell_height: u32,
+    antialias: bool,
+) -> RgbaImage {
+    let font = FontRef::try_from_slice(FONT_DATA).expect("bundled font is valid");
+    let scale = PxScale::from(cell_height as f32);
+    let scaled = font.as_scaled(scale);
+
+    let width = grid.width as u32 * cell_width;
+    let height = grid.height as u32 * cell_height;
+    let mut image = RgbaImage::new(width, height);
+
+    for (index, cell) in grid.cells.iter().enumerate() {
+        let origin_x = (index % grid.width as usize) as u32 * cell_width;
+        let origin_y = (index / grid.width as usize) as u32 * cell_height;
+
+        if let Some([r, g, b]) = cell.bg {
+            for y in origin_y..origin_y + cell_height {
+                for x in origin_x..origin_x + cell_width {
+                    image.put_pixel(x, y, Rgba([r, g, b, 255]));
+                }
+            }
+        }
+
+        if cell.ch.is_whitespace() {
+            continue;
+        }
+
+        // Center the advance horizontally and sit the glyph on the baseline.
+        let glyph_id = font.glyph_id(cell.ch);
+        let offset_x = (cell_width as f32 - scaled.h_advance(glyph_id)) / 2.;
+        let position = point(origin_x as f32 + offset_x, origin_y as f32 + scaled.ascent());
+        let Some(outline) = font.outline_glyph(glyph_id.with_scale_and_position(scale, position))
+        else {
+            continue;
+        };
+
+        let bounds = outline.px_bounds();
+        let [r, g, b] = cell.fg;
+        let alpha = cell.alpha.clamp(0., 1.);
+        outline.draw(|x, y, coverage| {
+            let x = bounds.min.x as i32 + x as i32;
+            let y = bounds.min.y as i32 + y as i32;
+            let inside_cell = (origin_x as i32..(origin_x + cell_width) as i32).contains(&x)
+                && (origin_y as i32..(origin_y + cell_height) as i32).contains(&y);
+            if !inside_cell {
+                return;
+            }
+
+            let pixel = image.get_pixel_mut(x as u32, y as u32);
+            if antialias {
+                *pixel = blend_over(*pixel, [r, g, b], coverage.clamp(0., 1.) * alpha);
+            } else if coverage >= COVERAGE_THRESHOLD {
+                *pixel = Rgba([r, g, b, (alpha * 255.).round() as u8]);
+            }
+        });
+    }
+
+    image
+}
+
+/// Composite `color` at `alpha` over `destination`.
+fn blend_over(destination: Rgba<u8>, color: [u8; 3], alpha: f32) -> Rgba<u8> {
+    let destination_alpha = destination.0[3] as f32 / 255.;
+    let out_alpha = alpha + destination_alpha * (1. - alpha);
+    if out_alpha <= 0. {
+        return destination;
+    }
+
+    let channel = |index: usize| {
+        let source = color[index] as f32 * alpha;
+        let below = destination.0[index] as f32 * destination_alpha * (1. - alpha);
+        ((source + below) / out_alpha).round() as u8
+    };
+    Rgba([channel(0), channel(1), channel(2), (out_alpha * 255.).round() as u8])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ascii::grid::CellGlyph;
+
+    #[test]
+    fn glyphs_are_stamped_in_their_cell() {
+        let mut cells = vec![CellGlyph::new('#', 1.), CellGlyph::new(' ', 1.)];
+        cells[0].fg = [255, 0, 0];
+        cells[1].bg = Some([0, 0, 255]);
+        let grid = GlyphGrid::new(2, 1, cells);
+
+        let image = render_grid_to_image(&grid, 8, 16, false);
+
+        assert_eq!((16, 16), image.dimensions());
+        let (left, right): (Vec<_>, Vec<_>) = image.enumerate_pixels().partition(|(x, ..)| *x < 8);
+        assert!(left.iter().any(|(.., pixel)| pixel.0 == [255, 0, 0, 255]));
+        assert!(left.iter().all(|(.., pixel)| pixel.0[3] == 0 || pixel.0 == [255, 0, 0, 255]));
+        assert!(right.iter().all(|(.., pixel)| pixel.0 == [0, 0, 255, 255]));
+    }
+
+    #[test]
+    fn cells_follow_font_metrics() {
+        let font = FontConfig { size: 20., antialias: false };
+        let (cell_width, cell_height) = font.cell_size();
+        // DejaVu Sans Mono advances a bit over half its line height.
+        assert_eq!(20, cell_height);
+        assert!((9..=12).contains(&cell_width), "{cell_width}");
+
+        let grid = GlyphGrid::new(3, 2, vec![CellGlyph::new('#', 1.); 6]);
+        let image = render_to_image(&grid, &font);
+        assert_eq!((3 * cell_width, 2 * cell_height), image.dimensions());
+    }
+
+    #[test]
+    fn antialiased_edges_are_partially_covered() {
+        let mut cell = CellGlyph::new('A', 1.);
+        cell.bg = Some([0, 0, 0]);
+        let grid = GlyphGrid::new(1, 1, vec![cell]);
+
+        let smooth = render_grid_to_image(&grid, 16, 32, true);
+        let jagged = render_grid_to_image(&grid, 16, 32, false);
+
+        let levels = |image: &RgbaImage| {
+            let mut levels: Vec<u8> = image.pixels().map(|pixel| pixel.0[0]).collect();
+            levels.sort_unstable();
+            levels.dedup();
+            levels
+        };
+        assert!(levels(&smooth).iter().any(|&level| level > 0 && level < 255));
+        assert_eq!(vec![0, 255], levels(&jagged));
+        assert!(smooth.pixels().all(|pixel| pixel.0[3] == 255));
+    }
+}
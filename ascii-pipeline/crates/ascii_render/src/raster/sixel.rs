@@ -0,0 +1,159 @@
+//! Sixel encoding of rasterized grids.
+
+use std::fmt::Write;
+
+use image::RgbaImage;
+
+use super::render_grid_to_image;
+use crate::ascii::grid::GlyphGrid;
+
+/// Device control string starting a sixel image whose unset pixels stay transparent.
+const INTRODUCER: &str = "\x1bP0;1;0q";
+
+/// String terminator ending the sixel image.
+const TERMINATOR: &str = "\x1b\\";
+
+/// Channel levels of the 6x6x6 color cube used as sixel palette.
+const CUBE_LEVELS: usize = 6;
+
+/// Encode an image as a sixel escape sequence.
+///
+/// Colors are snapped to a 216-entry color cube and pixels below half opacity are left unset.
+pub fn encode_sixel(image: &RgbaImage) -> String {
+    let (width, height) = image.dimensions();
+    let registers: Vec<Option<usize>> = image
+        .pixels()
+        .map(|pixel| {
+            let [r, g, b, a] = pixel.0;
+            (a >= 128).then(|| cube_index([r, g, b]))
+        })
+        .collect();
+
+    let mut used = [false; CUBE_LEVELS.pow(3)];
+    for register in registers.iter().flatten() {
+        used[*register] = true;
+    }
+
+    let mut sixel = String::from(INTRODUCER);
+    let _ = write!(sixel, "\"1;1;{};{}", width, height);
+    for register in (0..used.len()).filter(|register| used[*register]) {
+        let [r, g, b] = cube_color(register).map(|channel| channel as u32 * 100 / 255);
+        let _ = write!(sixel, "#{};2;{};{};{}", register, r, g, b);
+    }
+
+    let (width, height) = (width as usize, height as usize);
+    for band in (0..height).step_by(6) {
+        let band_rows = band..(band + 6).min(height);
+
+        let mut band_used = [false; CUBE_LEVELS.pow(3)];
+        for register in registers[band * width..band_rows.end * width].iter().flatten() {
+            band_used[*register] = true;
+        }
+
+        for register in (0..band_used.len()).filter(|register| band_used[*register]) {
+            let _ = write!(sixel, "#{}", register);
+            let columns = (0..width).map(|x| {
+                let bits = band_rows
+                    .clone()
+                    .filter(|y| registers[y * width + x] == Some(register))
+                    .fold(0, |bits, y| bits | 1 << (y - band));
+                (b'?' + bits) as char
+            });
+            push_run_length(&mut sixel, columns);
+            sixel.push('$');
+        }
+        sixel.push('-');
+    }
+
+    sixel.push_str(TERMINATOR);
+    sixel
+}
+
+impl GlyphGrid {
+    /// Rasterize the grid with `cell_width`x`cell_height` pixel cells and encode it as sixel.
+    pub fn to_sixel(&self, cell_width: u32, cell_height: u32) -> String {
+        encode_sixel(&render_grid_to_image(self, cell_width, cell_height, false))
+    }
+}
+
+/// Append sixel characters, collapsing longer runs into repeat introducers.
+fn push_run_length(sixel: &mut String, columns: impl Iterator<Item = char>) {
+    let mut run: Option<(char, usize)> = None;
+    let flush = |sixel: &mut String, (ch, count): (char, usize)| {
+        if count > 3 {
+            let _ = write!(sixel, "!{}{}", count, ch);
+        } else {
+            sixel.extend(std::iter::repeat_n(ch, count));
+        }
+    };
+
+    for ch in columns {
+        run = match run {
+            Some((current, count)) if current == ch => Some((current, count + 1)),
+            Some(previous) => {
+                flush(sixel, previous);
+                Some((ch, 1))
+            },
+            None => Some((ch, 1)),
+        };
+    }
+
+    if let Some(run) = run {
+        flush(sixel, run);
+    }
+}
+
+fn cube_index(color: [u8; 3]) -> usize {
+    let [r, g, b] = color.map(|channel| (channel as usize * (CUBE_LEVELS - 1) + 127) / 255);
+    (r * CUBE_LEVELS + g) * CUBE_LEVELS + b
+}
+
+fn cube_color(index: usize) -> [u8; 3] {
+    let level = |value: usize| (value * 255 / (CUBE_LEVELS - 1)) as u8;
+    [
+        level(index / (CUBE_LEVELS * CUBE_LEVELS)),
+        level(index / CUBE_LEVELS % CUBE_LEVELS),
+        level(index % CUBE_LEVELS),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgba;
+
+    use super::*;
+    use crate::ascii::grid::CellGlyph;
+
+    #[test]
+    fn sixel_is_wrapped_in_device_control_string() {
+        let mut cells = vec![CellGlyph::new('@', 1.); 4];
+        cells[1].fg = [255, 0, 0];
+        let grid = GlyphGrid::new(2, 2, cells);
+
+        let sixel = grid.to_sixel(8, 16);
+
+        assert!(sixel.starts_with("\x1bP"));
+        assert!(sixel.starts_with(INTRODUCER));
+        assert!(sixel.ends_with(TERMINATOR));
+        assert!(sixel.contains("\"1;1;16;32"));
+    }
+
+    #[test]
+    fn solid_image_is_run_length_encoded() {
+        let image = RgbaImage::from_pixel(10, 6, Rgba([255, 0, 0, 255]));
+
+        let sixel = encode_sixel(&image);
+
+        let red = cube_index([255, 0, 0]);
+        let expected =
+            format!("{}\"1;1;10;6#{red};2;100;0;0#{red}!10~$-{}", INTRODUCER, TERMINATOR);
+        assert_eq!(expected, sixel);
+    }
+
+    #[test]
+    fn cube_round_trips() {
+        for index in 0..CUBE_LEVELS.pow(3) {
+            assert_eq!(index, cube_index(cube_color(index)));
+        }
+    }
+}